@@ -0,0 +1,75 @@
+use rust_bert::pipelines::translation::Language;
+use serde::Deserialize;
+
+use crate::segmentation::split_sentences;
+
+/// How to summarize documents longer than the input of the model.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SummarizationStrategy {
+    /// Summarize the whole document at once; the model ignores the text beyond its maximum input
+    Truncate,
+    /// Summarize each chunk of the document and join the summaries
+    #[default]
+    Chunk,
+    /// Summarize each chunk, then summarize the joined summaries
+    MapReduce,
+}
+
+/// Splits `text` into chunks of whole sentences of at most `max_tokens` tokens, as counted by
+/// `count_tokens`. Each chunk starts with the last sentences of the previous chunk, up to
+/// `overlap` tokens, to keep some context. Sentences longer than `max_tokens` form a chunk on their own.
+pub(crate) fn chunk_text(text: &str, language: Option<Language>, max_tokens: usize, overlap: usize,
+                          count_tokens: impl Fn(&str) -> usize) -> Vec<&str> {
+    let sentences = split_sentences(text, language);
+    let tokens: Vec<usize> = sentences.iter().map(|s| count_tokens(s.text)).collect();
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < sentences.len() {
+        let mut last = first;
+        let mut total = tokens[first];
+        while last + 1 < sentences.len() && total + tokens[last + 1] <= max_tokens {
+            last += 1;
+            total += tokens[last];
+        }
+        chunks.push(&text[sentences[first].start..sentences[last].end]);
+        if last + 1 == sentences.len() {
+            break;
+        }
+        let mut next = last + 1;
+        let mut overlap_tokens = 0;
+        while next - 1 > first
+            && overlap_tokens + tokens[next - 1] <= overlap
+            && overlap_tokens + tokens[next - 1] + tokens[last + 1] <= max_tokens {
+            next -= 1;
+            overlap_tokens += tokens[next];
+        }
+        first = next;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_text_by_sentence() {
+        let words = |text: &str| text.split_whitespace().count();
+        let cases: [(&str, &str, usize, usize, &[&str]); 6] = [
+            ("empty", "", 4, 0, &[]),
+            ("single chunk", "One two. Three four.", 4, 0, &["One two. Three four."]),
+            ("without overlap", "One two. Three four. Five six. Seven eight.", 4, 0,
+             &["One two. Three four.", "Five six. Seven eight."]),
+            ("with overlap", "One two. Three four. Five six. Seven eight.", 4, 2,
+             &["One two. Three four.", "Three four. Five six.", "Five six. Seven eight."]),
+            ("overlap too large for the next sentence", "One two. Three four. Five six seven.", 4, 2,
+             &["One two. Three four.", "Five six seven."]),
+            ("long sentence", "One two. Three four five six seven. Eight nine.", 4, 2,
+             &["One two.", "Three four five six seven.", "Eight nine."]),
+        ];
+        for (name, text, max_tokens, overlap, expected) in cases {
+            assert_eq!(chunk_text(text, None, max_tokens, overlap, words), expected, "case '{}'", name);
+        }
+    }
+}
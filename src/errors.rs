@@ -0,0 +1,132 @@
+use std::fmt::{Display, Formatter};
+
+use actix_web::{HttpResponse, ResponseError};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use rust_bert::RustBertError;
+
+use crate::transport_structs::{ErrorCodes, ErrorDetail, ErrorResponse};
+
+/// Seconds clients are asked to wait before retrying a request rejected because the queue was full.
+const RETRY_AFTER_SECS: u64 = 5;
+
+/// Errors returned by the API. Each variant maps to an HTTP status and a stable error code
+/// which is serialized in the JSON error envelope.
+#[derive(Debug)]
+pub(crate) enum ApiError {
+    /// The request body is malformed or contains invalid values.
+    Validation(String),
+    /// The requested language is not supported by the translation models.
+    UnsupportedLanguage { value: String, accepted: Vec<String> },
+    /// The source language was omitted and could not be detected from the text.
+    UndetectedLanguage(String),
+    /// The requested model does not exist.
+    UnsupportedModel { value: String, accepted: Vec<String> },
+    /// The model could not be loaded, e.g. because the weights could not be downloaded.
+    ModelLoad(String),
+    /// The model was loaded, but failed to process the input.
+    Inference(String),
+    /// The service failed for a reason unrelated to the models, e.g. reading files.
+    Internal(String),
+    /// The requested resource, e.g. a job, does not exist.
+    NotFound(String),
+    /// The request lacks the credentials an admin endpoint requires.
+    Unauthorized(String),
+    /// Too much work is already waiting to be processed.
+    QueueFull(String),
+    /// The request did not complete in time.
+    Timeout(String),
+}
+
+impl ApiError {
+    pub(crate) fn model_load(e: RustBertError) -> ApiError {
+        println!("Failed to load model: {:?}", e);
+        ApiError::ModelLoad(e.to_string())
+    }
+
+    pub(crate) fn inference(e: RustBertError) -> ApiError {
+        println!("Inference failed: {:?}", e);
+        ApiError::Inference(e.to_string())
+    }
+
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            ApiError::Validation(_) => ErrorCodes::VALIDATION_ERROR,
+            ApiError::UnsupportedLanguage { .. } => ErrorCodes::UNSUPPORTED_LANGUAGE,
+            ApiError::UndetectedLanguage(_) => ErrorCodes::LANGUAGE_NOT_DETECTED,
+            ApiError::UnsupportedModel { .. } => ErrorCodes::UNSUPPORTED_MODEL,
+            ApiError::ModelLoad(_) => ErrorCodes::MODEL_LOAD_FAILED,
+            ApiError::Inference(_) => ErrorCodes::INFERENCE_FAILED,
+            ApiError::Internal(_) => ErrorCodes::INTERNAL_ERROR,
+            ApiError::NotFound(_) => ErrorCodes::NOT_FOUND,
+            ApiError::Unauthorized(_) => ErrorCodes::UNAUTHORIZED,
+            ApiError::QueueFull(_) => ErrorCodes::QUEUE_FULL,
+            ApiError::Timeout(_) => ErrorCodes::TIMEOUT,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            ApiError::UnsupportedLanguage { value, .. } => format!("Unsupported language: '{}'", value),
+            ApiError::UnsupportedModel { value, .. } => format!("Unsupported model: '{}'", value),
+            ApiError::Validation(message)
+            | ApiError::UndetectedLanguage(message)
+            | ApiError::ModelLoad(message)
+            | ApiError::Inference(message)
+            | ApiError::Internal(message)
+            | ApiError::NotFound(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::QueueFull(message)
+            | ApiError::Timeout(message) => message.clone()
+        }
+    }
+
+    /// The values which would have been accepted instead of the rejected one, if any.
+    fn accepted(&self) -> Option<Vec<String>> {
+        match self {
+            ApiError::UnsupportedLanguage { accepted, .. }
+            | ApiError::UnsupportedModel { accepted, .. } => Some(accepted.clone()),
+            _ => None
+        }
+    }
+
+    pub(crate) fn detail(&self) -> ErrorDetail {
+        ErrorDetail {
+            code: String::from(self.code()),
+            message: self.message(),
+            accepted: self.accepted(),
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Validation(_) | ApiError::UndetectedLanguage(_) => StatusCode::BAD_REQUEST,
+            ApiError::UnsupportedLanguage { .. } | ApiError::UnsupportedModel { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::ModelLoad(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Inference(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::QueueFull(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let ApiError::QueueFull(_) = self {
+            response.insert_header((RETRY_AFTER, RETRY_AFTER_SECS));
+        }
+        response.json(ErrorResponse {
+            status: String::from(ErrorCodes::STATUS_FAILED),
+            error: self.detail(),
+        })
+    }
+}
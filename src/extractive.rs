@@ -0,0 +1,96 @@
+use serde::Deserialize;
+
+/// Damping factor of the random walk over the sentence graph, as in PageRank.
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const CONVERGENCE: f64 = 1e-6;
+
+/// Number of sentences of extractive summaries when the request does not set it.
+pub(crate) const DEFAULT_SUMMARY_SENTENCES: usize = 3;
+pub(crate) const MAX_SUMMARY_SENTENCES: usize = 100;
+/// Largest number of sentences of a text to summarize extractively, as every pair of them is compared
+pub(crate) const MAX_INPUT_SENTENCES: usize = 1000;
+
+/// Whether summaries are generated by a model or made of sentences of the original text.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SummarizationMode {
+    #[default]
+    Abstractive,
+    Extractive,
+}
+
+/// Scores sentences by their centrality in the graph of cosine similarities between their
+/// embeddings (LexRank with edge weights, as in TextRank). Scores sum up to 1.
+pub(crate) fn centrality_scores(embeddings: &[Vec<f32>]) -> Vec<f64> {
+    let n = embeddings.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let similarities: Vec<Vec<f64>> = embeddings.iter()
+        .enumerate()
+        .map(|(i, a)| embeddings.iter()
+            .enumerate()
+            .map(|(j, b)| if i == j { 0.0 } else { cosine_similarity(a, b).max(0.0) })
+            .collect())
+        .collect();
+    let row_sums: Vec<f64> = similarities.iter().map(|row| row.iter().sum()).collect();
+
+    let mut scores = vec![1.0 / n as f64; n];
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<f64> = (0..n)
+            .map(|j| {
+                let incoming: f64 = (0..n)
+                    .map(|i| if row_sums[i] > 0.0 { scores[i] * similarities[i][j] / row_sums[i] } else { scores[i] / n as f64 })
+                    .sum();
+                (1.0 - DAMPING) / n as f64 + DAMPING * incoming
+            })
+            .collect();
+        let change: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+        scores = next;
+        if change < CONVERGENCE {
+            break;
+        }
+    }
+    scores
+}
+
+/// Indices of the `count` highest scores, in ascending order.
+pub(crate) fn top_indices(scores: &[f64], count: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..scores.len()).collect();
+    indices.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+    indices.truncate(count);
+    indices.sort();
+    indices
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Case<'a> = (&'a str, Vec<Vec<f32>>, usize, &'a [usize]);
+
+    #[test]
+    fn scores_central_sentences_highest() {
+        let cases: [Case; 4] = [
+            ("no sentences", vec![], 2, &[]),
+            ("fewer sentences than requested", vec![vec![1.0, 0.0]], 3, &[0]),
+            ("central sentence", vec![vec![1.0, 0.0], vec![0.7, 0.7], vec![0.0, 1.0]], 1, &[1]),
+            ("document order", vec![vec![0.0, 1.0], vec![1.0, 0.1], vec![1.0, 0.0], vec![0.9, 0.1]], 2, &[1, 3]),
+        ];
+        for (name, embeddings, count, expected) in cases {
+            let scores = centrality_scores(&embeddings);
+            assert_eq!(top_indices(&scores, count), expected, "case '{}'", name);
+            if !scores.is_empty() {
+                assert!((scores.iter().sum::<f64>() - 1.0).abs() < 1e-6, "case '{}'", name);
+            }
+        }
+    }
+}
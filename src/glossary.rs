@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::ApiError;
+
+/// Extension of glossary files in the `glossaries` directory.
+const GLOSSARY_EXTENSION: &str = "tsv";
+
+/// Punctuation ending a sentence, which terms restored at the end of a sentence are inserted before.
+const SENTENCE_END: [char; 7] = ['.', '!', '?', '…', '。', '！', '？'];
+
+/// Terms with a fixed translation. Terms are replaced by placeholders before translation,
+/// which the model copies unchanged, and the placeholders by the target terms afterwards.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Glossary {
+    /// Source and target terms, longest source term first
+    terms: Vec<(String, String)>,
+}
+
+impl Glossary {
+    /// Glossary of a request, where `do_not_translate` terms are kept as they are.
+    pub(crate) fn new(glossary: &HashMap<String, String>, do_not_translate: &[String]) -> Glossary {
+        let mut result = Glossary::default();
+        result.extend(glossary.iter().map(|(source, target)| (source.clone(), target.clone())));
+        result.extend(do_not_translate.iter().map(|term| (term.clone(), term.clone())));
+        result
+    }
+
+    /// Parses tab separated `source<TAB>target` lines. Lines without a target keep the term
+    /// untranslated; empty lines and lines starting with `#` are ignored.
+    pub(crate) fn parse(content: &str) -> Glossary {
+        let mut result = Glossary::default();
+        result.extend(content.lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| match line.split_once('\t') {
+                Some((source, target)) if !target.trim().is_empty() =>
+                    (source.trim().to_string(), target.trim().to_string()),
+                _ => (line.trim().to_string(), line.trim().to_string())
+            }));
+        result
+    }
+
+    /// Adds terms, replacing the target of terms which are already present.
+    pub(crate) fn extend(&mut self, terms: impl IntoIterator<Item=(String, String)>) {
+        for (source, target) in terms.into_iter().filter(|(source, _)| !source.is_empty()) {
+            self.terms.retain(|(s, _)| *s != source);
+            self.terms.push((source, target));
+        }
+        self.terms.sort_by_key(|(source, _)| std::cmp::Reverse(source.len()));
+    }
+
+    pub(crate) fn terms(&self) -> impl Iterator<Item=(String, String)> + '_ {
+        self.terms.iter().cloned()
+    }
+
+    /// Replaces the whole word occurrences of the source terms in `text` with placeholders.
+    /// Returns the text and the target term of each placeholder.
+    pub(crate) fn protect(&self, text: &str) -> (String, Vec<&str>) {
+        let mut protected = String::with_capacity(text.len());
+        let mut targets = Vec::new();
+        let mut i = 0;
+        while i < text.len() {
+            let at_word_start = !text[..i].chars().next_back().is_some_and(char::is_alphanumeric);
+            let term = self.terms.iter().find(|(source, _)| at_word_start
+                && text[i..].starts_with(source.as_str())
+                && !text[i + source.len()..].chars().next().is_some_and(char::is_alphanumeric));
+            match term {
+                Some((source, target)) => {
+                    protected.push_str(&placeholder(targets.len()));
+                    targets.push(target.as_str());
+                    i += source.len();
+                }
+                None => {
+                    let c = text[i..].chars().next().unwrap();
+                    protected.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        (protected, targets)
+    }
+
+    /// Replaces the placeholders of a translation with their target terms. Terms whose placeholder the
+    /// model dropped or mangled are appended to the sentence, before its final punctuation, rather than lost.
+    pub(crate) fn restore(translation: &str, targets: &[&str]) -> String {
+        let mut restored = translation.to_string();
+        let mut missing = Vec::new();
+        for (index, target) in targets.iter().enumerate() {
+            let placeholder = placeholder(index);
+            match restored.find(&placeholder) {
+                Some(position) => restored.replace_range(position..position + placeholder.len(), target),
+                None => missing.push(*target),
+            }
+        }
+        if !missing.is_empty() {
+            println!("Placeholders of {:?} missing in translation '{}', appending the terms", missing, translation);
+            let end = restored.trim_end_matches(|c: char| c.is_whitespace() || SENTENCE_END.contains(&c)).len();
+            let insert = if end == 0 { missing.join(" ") } else { format!(" {}", missing.join(" ")) };
+            restored.insert_str(end, &insert);
+        }
+        restored
+    }
+}
+
+fn placeholder(index: usize) -> String {
+    format!("ZXQ{}QXZ", index)
+}
+
+/// Glossaries loaded at startup, which requests refer to by name.
+#[derive(Debug, Default)]
+pub(crate) struct Glossaries {
+    named: HashMap<String, Glossary>,
+}
+
+impl Glossaries {
+    /// Loads every `<name>.tsv` file of `directory`. An empty directory setting loads nothing.
+    pub(crate) fn load(directory: &str) -> Result<Glossaries, String> {
+        let mut named = HashMap::new();
+        if directory.is_empty() {
+            return Ok(Glossaries { named });
+        }
+        let entries = fs::read_dir(directory)
+            .map_err(|e| format!("Cannot read glossaries from '{}': {}", directory, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(GLOSSARY_EXTENSION) {
+                continue;
+            }
+            let name = glossary_name(&path)?;
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Cannot read glossary '{}': {}", path.display(), e))?;
+            println!("Loaded glossary {}", name);
+            named.insert(name, Glossary::parse(&content));
+        }
+        Ok(Glossaries { named })
+    }
+
+    /// Combines the named glossaries, in order, with the terms of a request.
+    pub(crate) fn resolve(&self, names: &[String], request: Glossary) -> Result<Glossary, ApiError> {
+        let mut result = Glossary::default();
+        for name in names {
+            let glossary = self.named.get(name)
+                .ok_or_else(|| ApiError::Validation(format!("Unknown glossary '{}'", name)))?;
+            result.extend(glossary.terms());
+        }
+        result.extend(request.terms());
+        Ok(result)
+    }
+}
+
+fn glossary_name(path: &Path) -> Result<String, String> {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .ok_or(format!("Invalid glossary file name '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protects_and_restores_terms() {
+        let glossary = Glossary::parse("Rust Bert\tRust-Bert\nOnePoint\ncloud\tWolke\n# comment\n");
+        let cases: [(&str, &str, &str); 5] = [
+            ("Use Rust Bert today", "Use ZXQ0QXZ today", "Use Rust-Bert today"),
+            ("OnePoint and the cloud.", "ZXQ0QXZ and the ZXQ1QXZ.", "OnePoint and the Wolke."),
+            ("Clouds and cloudy skies", "Clouds and cloudy skies", "Clouds and cloudy skies"),
+            ("Rust is fast", "Rust is fast", "Rust is fast"),
+            ("Über OnePoint", "Über ZXQ0QXZ", "Über OnePoint"),
+        ];
+        for (input, protected, restored) in cases {
+            let (text, targets) = glossary.protect(input);
+            assert_eq!(text, protected, "input '{}'", input);
+            assert_eq!(Glossary::restore(&text, &targets), restored, "input '{}'", input);
+        }
+    }
+
+    #[test]
+    fn appends_terms_of_missing_placeholders() {
+        let cases: [(&str, &[&str], &str); 4] = [
+            ("Verwenden Sie heute ZXQ0QXZ.", &["Rust-Bert"], "Verwenden Sie heute Rust-Bert."),
+            ("Verwenden Sie es heute.", &["Rust-Bert"], "Verwenden Sie es heute Rust-Bert."),
+            ("ZXQ1QXZ und die Cloud!", &["OnePoint", "Wolke"], "Wolke und die Cloud OnePoint!"),
+            ("", &["OnePoint"], "OnePoint"),
+        ];
+        for (translation, targets, restored) in cases {
+            assert_eq!(Glossary::restore(translation, targets), restored, "translation '{}'", translation);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt::time::timeout;
+use threadpool::ThreadPool;
+use tokio::sync::oneshot;
+
+use crate::errors::ApiError;
+
+/// Process-wide pool of the threads running the models. At most `max_queue` tasks wait for a free
+/// thread, further tasks are rejected so that clients back off instead of piling up work.
+pub(crate) struct InferencePool {
+    pool: ThreadPool,
+    /// Tasks submitted which have not started yet
+    queued: Arc<AtomicUsize>,
+    max_queue: usize,
+    /// How long requests wait for their result unless they set their own timeout
+    default_timeout: Duration,
+}
+
+impl InferencePool {
+    pub(crate) fn new(threads: usize, max_queue: usize, default_timeout: Duration) -> Result<Self, String> {
+        if threads == 0 {
+            return Err(String::from("inference_threads must be at least 1"));
+        }
+        if max_queue == 0 {
+            return Err(String::from("max_inference_queue must be at least 1, or every request is rejected"));
+        }
+        Ok(InferencePool {
+            pool: ThreadPool::with_name(String::from("inference"), threads),
+            queued: Arc::new(AtomicUsize::new(0)),
+            max_queue,
+            default_timeout,
+        })
+    }
+
+    /// Queues `task`, unless `max_queue` tasks are already waiting.
+    pub(crate) fn execute<F>(&self, task: F) -> Result<(), ApiError> where F: FnOnce() + Send + 'static {
+        self.queued.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+            if queued < self.max_queue { Some(queued + 1) } else { None }
+        }).map_err(|queued| ApiError::QueueFull(format!("{} inference tasks are already queued", queued)))?;
+        let queued = self.queued.clone();
+        self.pool.execute(move || {
+            queued.fetch_sub(1, Ordering::SeqCst);
+            task();
+        });
+        Ok(())
+    }
+
+    /// Runs `work` on the pool and waits for its result without blocking the calling actix worker,
+    /// which keeps serving other requests in the meantime. If the returned future is dropped before
+    /// `work` starts, because the request timed out or the client went away, `work` is skipped.
+    pub(crate) async fn run<T, F>(&self, work: F) -> Result<T, ApiError>
+        where T: Send + 'static, F: FnOnce() -> Result<T, ApiError> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        self.execute(move || {
+            if tx.is_closed() {
+                println!("Skipping inference task, nobody is waiting for its result");
+                return;
+            }
+            let _ = tx.send(work());
+        })?;
+        rx.await.unwrap_or_else(|_| worker_failed())
+    }
+
+    /// Waits for `work` for `timeout_ms` milliseconds if set, for the default timeout otherwise.
+    pub(crate) async fn within<T>(&self, timeout_ms: Option<u64>, work: impl Future<Output=Result<T, ApiError>>)
+                                  -> Result<T, ApiError> {
+        let limit = match timeout_ms {
+            Some(0) => return Err(ApiError::Validation(String::from("timeout_ms must be positive"))),
+            Some(ms) => Duration::from_millis(ms),
+            None => self.default_timeout,
+        };
+        timeout(limit, work).await.unwrap_or_else(|_| Err(ApiError::Timeout(
+            format!("The request did not complete within {} ms", limit.as_millis()))))
+    }
+}
+
+pub(crate) fn worker_failed<T>() -> Result<T, ApiError> {
+    Err(ApiError::Inference(String::from("The inference worker stopped unexpectedly")))
+}
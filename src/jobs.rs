@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::errors::ApiError;
+use crate::inference::worker_failed;
+use crate::model_registry::ModelRegistry;
+use crate::transport_structs::{ErrorDetail, JobResponse};
+
+/// Receives the fraction of the work done and returns whether the job was cancelled meanwhile,
+/// in which case the work stops before its next model call.
+pub(crate) type Progress<'a> = &'a dyn Fn(f32) -> bool;
+
+/// Work of a job. Reports the fraction of the work done through its second argument and
+/// returns the response body of the synchronous endpoint of the task.
+pub(crate) type JobTask = Box<dyn FnOnce(&ModelRegistry, Progress) -> Result<Value, ApiError> + Send>;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+struct Job {
+    task: &'static str,
+    state: JobState,
+    progress: f32,
+    result: Option<Value>,
+    error: Option<ErrorDetail>,
+    /// Set once the job completed, failed or was cancelled
+    finished: Option<Instant>,
+}
+
+impl Job {
+    fn response(&self, id: &str) -> JobResponse {
+        JobResponse {
+            id: id.to_string(),
+            task: self.task.to_string(),
+            state: self.state,
+            progress: self.progress,
+            result: self.result.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Jobs submitted through `/jobs`. At most `max_queued` jobs wait for the inference pool at a time,
+/// and finished jobs are kept for `retention` so that clients can fetch their result.
+pub(crate) struct Jobs {
+    jobs: Mutex<HashMap<String, Job>>,
+    max_queued: usize,
+    retention: Duration,
+}
+
+impl Jobs {
+    pub(crate) fn new(max_queued: usize, retention: Duration) -> Self {
+        Jobs {
+            jobs: Mutex::new(HashMap::new()),
+            max_queued,
+            retention,
+        }
+    }
+
+    /// Registers a queued job running `task`, unless the queue is full.
+    pub(crate) fn add(&self, task: &'static str) -> Result<JobResponse, ApiError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let now = Instant::now();
+        jobs.retain(|_, job| job.finished.is_none_or(|finished| now - finished < self.retention));
+        let queued = jobs.values().filter(|job| job.state == JobState::Queued).count();
+        if queued >= self.max_queued {
+            return Err(ApiError::QueueFull(format!("{} jobs are already queued, try again later", queued)));
+        }
+        let id = Uuid::new_v4().to_string();
+        let job = Job { task, state: JobState::Queued, progress: 0.0, result: None, error: None, finished: None };
+        let response = job.response(&id);
+        jobs.insert(id, job);
+        Ok(response)
+    }
+
+    /// Forgets a job, e.g. because it could not be queued on the inference pool.
+    pub(crate) fn remove(&self, id: &str) {
+        self.jobs.lock().unwrap().remove(id);
+    }
+
+    pub(crate) fn status(&self, id: &str) -> Result<JobResponse, ApiError> {
+        self.jobs.lock().unwrap().get(id)
+            .map(|job| job.response(id))
+            .ok_or_else(|| unknown_job(id))
+    }
+
+    /// Cancels a queued or running job. A running job stops after its current model call where the
+    /// task checks for it, and its result is discarded. Finished jobs are left as they are.
+    pub(crate) fn cancel(&self, id: &str) -> Result<JobResponse, ApiError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id).ok_or_else(|| unknown_job(id))?;
+        if job.finished.is_none() {
+            job.state = JobState::Cancelled;
+            job.finished = Some(Instant::now());
+        }
+        Ok(job.response(id))
+    }
+
+    /// Runs the job `id` on the calling thread, unless it was cancelled while queued.
+    pub(crate) fn run(&self, id: &str, task: JobTask, registry: &ModelRegistry) {
+        let started = self.update(id, |job| {
+            let queued = job.state == JobState::Queued;
+            if queued {
+                job.state = JobState::Running;
+            }
+            queued
+        });
+        if started != Some(true) {
+            return;
+        }
+        let progress = |fraction: f32| {
+            self.update(id, |job| {
+                if job.state == JobState::Running {
+                    job.progress = fraction;
+                }
+                job.state == JobState::Cancelled
+            }).unwrap_or(true)
+        };
+        let result = catch_unwind(AssertUnwindSafe(|| task(registry, &progress)))
+            .unwrap_or_else(|_| worker_failed());
+        self.update(id, |job| {
+            if job.state != JobState::Running {
+                return;
+            }
+            match result {
+                Ok(value) => {
+                    job.state = JobState::Completed;
+                    job.progress = 1.0;
+                    job.result = Some(value);
+                }
+                Err(e) => {
+                    job.state = JobState::Failed;
+                    job.error = Some(e.detail());
+                }
+            }
+            job.finished = Some(Instant::now());
+        });
+    }
+
+    fn update<T>(&self, id: &str, f: impl FnOnce(&mut Job) -> T) -> Option<T> {
+        self.jobs.lock().unwrap().get_mut(id).map(f)
+    }
+}
+
+fn unknown_job(id: &str) -> ApiError {
+    ApiError::NotFound(format!("Unknown job '{}'", id))
+}
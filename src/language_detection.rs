@@ -0,0 +1,692 @@
+use rust_bert::pipelines::translation::Language;
+
+use crate::languages::SupportedLanguage;
+
+/// Writing systems, told apart by Unicode block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Bengali,
+    Gurmukhi,
+    Gujarati,
+    Oriya,
+    Tamil,
+    Kannada,
+    Malayalam,
+    Sinhala,
+    Thai,
+    Lao,
+    Myanmar,
+    Georgian,
+    Ethiopic,
+    Khmer,
+    Hangul,
+    /// Hiragana and Katakana
+    Kana,
+    Han,
+}
+
+/// Unicode blocks of the scripts, Latin letters excepted.
+const SCRIPT_RANGES: [(char, char, Script); 25] = [
+    ('\u{0370}', '\u{03FF}', Script::Greek),
+    ('\u{0400}', '\u{052F}', Script::Cyrillic),
+    ('\u{0530}', '\u{058F}', Script::Armenian),
+    ('\u{0590}', '\u{05FF}', Script::Hebrew),
+    ('\u{0600}', '\u{06FF}', Script::Arabic),
+    ('\u{0750}', '\u{077F}', Script::Arabic),
+    ('\u{0900}', '\u{097F}', Script::Devanagari),
+    ('\u{0980}', '\u{09FF}', Script::Bengali),
+    ('\u{0A00}', '\u{0A7F}', Script::Gurmukhi),
+    ('\u{0A80}', '\u{0AFF}', Script::Gujarati),
+    ('\u{0B00}', '\u{0B7F}', Script::Oriya),
+    ('\u{0B80}', '\u{0BFF}', Script::Tamil),
+    ('\u{0C80}', '\u{0CFF}', Script::Kannada),
+    ('\u{0D00}', '\u{0D7F}', Script::Malayalam),
+    ('\u{0D80}', '\u{0DFF}', Script::Sinhala),
+    ('\u{0E00}', '\u{0E7F}', Script::Thai),
+    ('\u{0E80}', '\u{0EFF}', Script::Lao),
+    ('\u{1000}', '\u{109F}', Script::Myanmar),
+    ('\u{10A0}', '\u{10FF}', Script::Georgian),
+    ('\u{1100}', '\u{11FF}', Script::Hangul),
+    ('\u{1200}', '\u{137F}', Script::Ethiopic),
+    ('\u{1780}', '\u{17FF}', Script::Khmer),
+    ('\u{3040}', '\u{30FF}', Script::Kana),
+    ('\u{4E00}', '\u{9FFF}', Script::Han),
+    ('\u{AC00}', '\u{D7AF}', Script::Hangul),
+];
+
+/// Scripts written by a single supported language, which decide on their own.
+const SCRIPT_LANGUAGES: [(Script, Language); 17] = [
+    (Script::Greek, Language::Greek),
+    (Script::Armenian, Language::Armenian),
+    (Script::Bengali, Language::Bengali),
+    (Script::Gurmukhi, Language::Panjabi),
+    (Script::Gujarati, Language::Gujarati),
+    (Script::Oriya, Language::Oriya),
+    (Script::Tamil, Language::Tamil),
+    (Script::Kannada, Language::Kannada),
+    (Script::Malayalam, Language::Malayalam),
+    (Script::Sinhala, Language::Sinhala),
+    (Script::Thai, Language::Thai),
+    (Script::Lao, Language::Lao),
+    (Script::Myanmar, Language::Burmese),
+    (Script::Georgian, Language::Georgian),
+    (Script::Ethiopic, Language::Amharic),
+    (Script::Khmer, Language::CentralKhmer),
+    (Script::Hangul, Language::Korean),
+];
+
+/// Evidence for a language sharing its script with other languages: frequent words, character
+/// trigrams of words padded with `_`, and characters which are frequent in the language.
+struct Profile {
+    language: Language,
+    script: Script,
+    words: &'static [&'static str],
+    trigrams: &'static [&'static str],
+    chars: &'static str,
+}
+
+const PROFILES: [Profile; 38] = [
+    Profile {
+        language: Language::English,
+        script: Script::Latin,
+        words: &[
+            "the", "and", "of", "to", "a", "an", "is", "in", "that", "it", "was", "for", "with", "as", "are",
+            "this", "be", "on", "have", "has", "had", "not", "by", "from", "at", "or", "which", "but", "they",
+            "you", "we", "i", "he", "she", "his", "her", "my", "your", "our", "were", "will", "would", "can",
+            "their", "been", "there", "what", "about", "do", "does", "how", "hello", "am", "me",
+        ],
+        trigrams: &[
+            "_th", "the", "he_", "and", "nd_", "ing", "ng_", "_of", "of_", "_wh", "tha", "hat", "ed_", "ly_",
+            "ght", "_yo", "you", "ay_", "ow_", "_wi", "ith", "wit", "_sh", "ck_", "oul", "uld", "_i_", "ve_",
+        ],
+        chars: "",
+    },
+    Profile {
+        language: Language::French,
+        script: Script::Latin,
+        words: &[
+            "le", "la", "les", "et", "est", "de", "des", "du", "un", "une", "que", "qui", "dans", "pour",
+            "pas", "sur", "au", "aux", "avec", "ce", "cette", "il", "elle", "nous", "vous", "ils", "sont",
+            "mais", "ou", "par", "plus", "ne", "se", "son", "sa", "ses", "été", "être", "leur", "comme",
+            "je", "tu", "suis", "es", "me", "te", "moi", "toi", "mon", "ma", "mes", "ton", "ta", "on", "y",
+            "en", "ai", "très", "bien", "ça", "va", "comment", "bonjour", "merci", "oui", "non", "c'est",
+        ],
+        trigrams: &[
+            "_le", "les", "es_", "_de", "ent", "nt_", "_qu", "que", "ue_", "_et", "et_", "eau", "ais", "ait",
+            "_vo", "vou", "ous", "_je", "je_", "_ce", "oir", "eux", "aux", "_pa", "our", "ço", "ça_",
+        ],
+        chars: "çêœèàùâîû",
+    },
+    Profile {
+        language: Language::German,
+        script: Script::Latin,
+        words: &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "einen", "zu", "den", "dem", "mit",
+            "sich", "auf", "für", "von", "im", "es", "sie", "wir", "ich", "auch", "dass", "sind", "war",
+            "wird", "aber", "noch", "wie", "bei", "oder", "nach", "werden", "hat", "kann", "aus", "über",
+            "du", "er", "bin", "habe", "hast", "mein", "meine", "dein", "sehr", "gut", "hallo", "geht",
+        ],
+        trigrams: &[
+            "der", "er_", "die", "ie_", "ein", "ich", "ch_", "sch", "_un", "und", "nd_", "en_", "_zu", "_ge",
+            "cht", "ung", "ng_", "ist", "st_", "_ni", "nic", "auf", "eit", "_ve", "ver", "_si", "ße",
+        ],
+        chars: "ßäöü",
+    },
+    Profile {
+        language: Language::Dutch,
+        script: Script::Latin,
+        words: &[
+            "de", "het", "een", "en", "van", "is", "niet", "dat", "die", "op", "te", "zijn", "met", "voor",
+            "er", "maar", "ook", "als", "bij", "aan", "om", "wat", "wordt", "worden", "hij", "zij", "wij",
+            "ik", "je", "naar", "heeft", "deze", "door", "nog", "kan", "dit", "zo", "uit", "geen", "ben",
+            "heb", "mijn", "jij", "hoe", "gaat", "goed", "hallo", "erg",
+        ],
+        trigrams: &[
+            "_de", "_he", "het", "_ee", "een", "en_", "_va", "van", "an_", "ij_", "oor", "voo", "aar", "ijk",
+            "_zi", "nie", "iet", "dat", "ge_", "_ge", "cht", "sch", "ee_", "aan", "ijn", "zij", "oek", "uit",
+        ],
+        chars: "",
+    },
+    Profile {
+        language: Language::Spanish,
+        script: Script::Latin,
+        words: &[
+            "el", "la", "los", "las", "y", "es", "de", "del", "en", "un", "una", "que", "no", "para", "con",
+            "por", "se", "más", "pero", "al", "fue", "son", "como", "él", "ella", "está", "muy", "también",
+            "su", "sus", "esto", "lo", "le", "hay", "ya", "cuando", "sin", "sobre", "entre", "porque", "a",
+            "yo", "tú", "tengo", "tiene", "estoy", "soy", "mi", "tu", "me", "te", "hola", "qué", "cómo", "bien", "gracias",
+        ],
+        trigrams: &[
+            "_de", "de_", "_la", "la_", "_el", "el_", "_qu", "que", "ue_", "os_", "as_", "_lo", "los", "_es",
+            "es_", "ión", "ció", "_co", "con", "_en", "en_", "par", "ara", "ado", "ada", "_po", "por", "una", "_y_", "rro", "ngo",
+        ],
+        chars: "ñ¿¡áíóú",
+    },
+    Profile {
+        language: Language::Portuguese,
+        script: Script::Latin,
+        words: &[
+            "o", "a", "os", "as", "e", "é", "de", "do", "da", "dos", "das", "em", "no", "na", "nos", "um",
+            "uma", "que", "não", "para", "com", "por", "se", "mais", "mas", "ao", "foi", "são", "como",
+            "ele", "ela", "está", "muito", "também", "seu", "sua", "isso", "pelo", "pela", "você", "eu",
+            "tenho", "estou", "meu", "minha", "olá", "obrigado", "bem", "tudo",
+        ],
+        trigrams: &[
+            "_de", "de_", "_qu", "que", "_do", "do_", "_da", "da_", "ão_", "ção", "_co", "com", "_um", "um_",
+            "ões", "_nã", "não", "par", "ara", "nte", "_os", "os_", "as_", "_é_", "em_", "_em", "lha", "nho",
+        ],
+        chars: "ãõçêâáéíóú",
+    },
+    Profile {
+        language: Language::Italian,
+        script: Script::Latin,
+        words: &[
+            "il", "lo", "la", "gli", "le", "e", "è", "di", "del", "della", "dei", "un", "una", "che", "non",
+            "per", "con", "in", "nel", "nella", "sono", "ma", "più", "come", "anche", "questo", "questa",
+            "alla", "al", "si", "ha", "hanno", "era", "essere", "molto", "suo", "sua", "loro", "perché", "ci",
+            "a", "io", "tu", "mi", "ti", "ho", "hai", "mio", "mia", "ciao", "grazie", "bene", "sei", "sta",
+        ],
+        trigrams: &[
+            "_di", "di_", "_il", "il_", "_ch", "che", "he_", "_de", "del", "ell", "lla", "_co", "con", "_pe",
+            "per", "er_", "_no", "non", "on_", "zio", "one", "ne_", "_un", "gli", "_gl", "tto", "zza", "cci",
+        ],
+        chars: "èàòùì",
+    },
+    Profile {
+        language: Language::Polish,
+        script: Script::Latin,
+        words: &[
+            "i", "w", "nie", "na", "się", "z", "do", "to", "że", "jest", "o", "jak", "ale", "po", "co", "tak",
+            "za", "od", "przez", "dla", "jego", "ja", "ty", "on", "ona", "my", "wy", "oni", "mam", "jestem",
+            "bardzo", "czy", "tylko", "już", "cześć", "dzień", "dobry",
+        ],
+        trigrams: &[
+            "_ni", "nie", "ie_", "_w_", "_si", "się", "_pr", "prz", "rze", "_za", "_na", "na_", "ów_", "ość",
+            "ści", "_je", "jes", "est", "ego", "go_", "czy", "szy", "ych", "_po", "dzi", "wie",
+        ],
+        chars: "ąćęłńśźż",
+    },
+    Profile {
+        language: Language::Czech,
+        script: Script::Latin,
+        words: &[
+            "a", "je", "se", "na", "v", "to", "že", "s", "z", "do", "o", "jak", "ale", "po", "co", "tak",
+            "za", "od", "pro", "by", "byl", "jsem", "jsi", "jsou", "není", "já", "ty", "on", "ona", "my",
+            "vy", "oni", "mám", "velmi", "jen", "už", "ahoj", "děkuji", "dobrý",
+        ],
+        trigrams: &[
+            "_je", "je_", "_se", "se_", "_na", "_pr", "pro", "_ne", "ost", "ých", "ch_", "_a_", "_v_", "ní_",
+            "_že", "že_", "byl", "ého", "ho_", "jak", "ak_", "při", "ová", "stv", "tví", "ěl_",
+        ],
+        chars: "ěřůčšžýň",
+    },
+    Profile {
+        language: Language::Swedish,
+        script: Script::Latin,
+        words: &[
+            "och", "att", "det", "i", "en", "ett", "är", "som", "på", "för", "med", "av", "till", "den",
+            "inte", "har", "de", "jag", "du", "han", "hon", "vi", "ni", "var", "om", "men", "så", "kan",
+            "vad", "hur", "mycket", "hej", "tack", "bra",
+        ],
+        trigrams: &[
+            "och", "ch_", "_at", "att", "tt_", "det", "_fö", "för", "ör_", "som", "om_", "jag", "ag_", "_är",
+            "är_", "int", "nte", "med", "_på", "på_", "ett", "nde", "lig",
+        ],
+        chars: "åäö",
+    },
+    Profile {
+        language: Language::Danish,
+        script: Script::Latin,
+        words: &[
+            "og", "at", "det", "i", "en", "et", "er", "som", "på", "for", "med", "af", "til", "den", "ikke",
+            "har", "de", "jeg", "du", "han", "hun", "vi", "var", "om", "men", "så", "kan", "hvad", "hvordan",
+            "meget", "hej", "tak", "godt", "mig", "dig",
+        ],
+        trigrams: &[
+            "_og", "og_", "_at", "at_", "det", "et_", "jeg", "eg_", "_ik", "ikk", "kke", "ke_", "for", "med",
+            "_på", "på_", "som", "_er", "er_", "ere", "har", "hvo", "lig",
+        ],
+        chars: "æøå",
+    },
+    Profile {
+        language: Language::Finnish,
+        script: Script::Latin,
+        words: &[
+            "ja", "on", "ei", "se", "että", "oli", "hän", "minä", "sinä", "me", "te", "he", "ovat", "mutta",
+            "kun", "niin", "tai", "myös", "kuin", "jo", "olen", "olet", "mitä", "miten", "hyvin", "kiitos",
+            "moi", "hyvää", "paljon",
+        ],
+        trigrams: &[
+            "_ja", "ja_", "_on", "on_", "_ei", "ei_", "oli", "lla", "llä", "ssa", "ssä", "sta", "stä", "kun",
+            "inä", "ään", "än_", "sen", "tta", "ttä", "nen", "aan", "an_", "ais", "uks", "iin",
+        ],
+        chars: "äö",
+    },
+    Profile {
+        language: Language::Hungarian,
+        script: Script::Latin,
+        words: &[
+            "a", "az", "és", "hogy", "nem", "egy", "van", "is", "meg", "de", "ez", "azt", "mint", "csak",
+            "már", "még", "vagy", "volt", "én", "te", "ő", "mi", "ti", "ők", "vagyok", "nagyon", "szia",
+            "köszönöm", "jó", "hogy", "mit",
+        ],
+        trigrams: &[
+            "_az", "az_", "_és", "és_", "hog", "ogy", "gy_", "nem", "em_", "egy", "van", "sze", "ett", "tt_",
+            "ak_", "ek_", "meg", "eg_", "ben", "ból", "nak", "nek",
+        ],
+        chars: "őűáéíóöü",
+    },
+    Profile {
+        language: Language::Romanian,
+        script: Script::Latin,
+        words: &[
+            "și", "şi", "în", "de", "la", "că", "nu", "pe", "un", "o", "cu", "se", "ce", "mai", "din", "este",
+            "sunt", "eu", "tu", "el", "ea", "noi", "voi", "ei", "am", "foarte", "bună", "mulțumesc", "ești",
+            "care", "pentru",
+        ],
+        trigrams: &[
+            "_și", "și_", "_în", "în_", "că_", "_nu", "nu_", "_pe", "pe_", "ul_", "lui", "ție", "ții", "ce_",
+            "are", "ată", "_cu", "cu_", "ște", "ăți", "ent", "est",
+        ],
+        chars: "ăâîșțşţ",
+    },
+    Profile {
+        language: Language::Turkish,
+        script: Script::Latin,
+        words: &[
+            "bir", "ve", "bu", "da", "de", "ne", "için", "ile", "çok", "ben", "sen", "o", "biz", "siz", "onlar",
+            "var", "yok", "değil", "mi", "mı", "nasıl", "merhaba", "teşekkürler", "iyi", "ama", "gibi",
+        ],
+        trigrams: &[
+            "_bi", "bir", "ir_", "_ve", "ve_", "_bu", "bu_", "ler", "lar", "ını", "ini", "ın_", "yor", "ıyo",
+            "iyo", "_ol", "ola", "lan", "ış_", "içi", "çin", "dır", "dir",
+        ],
+        chars: "ğışçöü",
+    },
+    Profile {
+        language: Language::Indonesian,
+        script: Script::Latin,
+        words: &[
+            "yang", "dan", "di", "ini", "itu", "dengan", "untuk", "tidak", "ada", "ke", "dari", "dalam", "akan",
+            "saya", "kamu", "anda", "dia", "kami", "kita", "mereka", "apa", "bagaimana", "sangat", "terima",
+            "kasih", "selamat", "sudah", "juga", "bisa",
+        ],
+        trigrams: &[
+            "_ya", "yan", "ang", "ng_", "_da", "dan", "an_", "_di", "di_", "ini", "ni_", "itu", "tu_", "den",
+            "eng", "nga", "aka", "kan", "_ke", "ke_", "_me", "men", "mem", "ada", "ah_",
+        ],
+        chars: "",
+    },
+    Profile {
+        language: Language::Vietnamese,
+        script: Script::Latin,
+        words: &[
+            "và", "của", "là", "có", "không", "được", "cho", "những", "các", "một", "người", "này", "trong",
+            "với", "đã", "tôi", "bạn", "để", "rất", "xin", "chào", "cảm", "ơn", "khỏe", "con", "em", "anh",
+            "chị", "ở", "thì", "cũng", "nó", "đi", "làm", "gì", "nào", "ai",
+        ],
+        trigrams: &[],
+        chars: "ôơưđăạảấầẩẫậắằẳẵặẹẻẽếềểễệỉịọỏốồổỗộớờởỡợụủứừửữựỳỵỷỹ",
+    },
+    Profile {
+        language: Language::Russian,
+        script: Script::Cyrillic,
+        words: &[
+            "и", "в", "не", "на", "я", "что", "он", "с", "как", "это", "по", "но", "они", "мы", "вы", "она",
+            "так", "его", "все", "был", "было", "для", "да", "нет", "у", "к", "же", "бы", "есть", "только",
+            "или", "когда", "уже", "привет", "спасибо", "хорошо", "очень", "меня", "тебя",
+        ],
+        trigrams: &[],
+        chars: "ыэъё",
+    },
+    Profile {
+        language: Language::Ukrainian,
+        script: Script::Cyrillic,
+        words: &[
+            "і", "в", "не", "на", "я", "що", "він", "з", "як", "це", "по", "але", "вони", "ми", "ви", "вона",
+            "так", "його", "все", "був", "для", "ні", "у", "до", "та", "й", "є", "або", "коли", "вже",
+            "привіт", "дякую", "добре", "дуже", "справи", "мене",
+        ],
+        trigrams: &[],
+        chars: "іїєґ",
+    },
+    Profile {
+        language: Language::Belarusian,
+        script: Script::Cyrillic,
+        words: &[
+            "і", "у", "не", "на", "я", "што", "ён", "з", "як", "гэта", "па", "але", "яны", "мы", "вы", "яна",
+            "так", "яго", "усе", "быў", "для", "ці", "ўжо", "дзякуй", "вельмі", "прывітанне",
+        ],
+        trigrams: &[],
+        chars: "ўі",
+    },
+    Profile {
+        language: Language::Bulgarian,
+        script: Script::Cyrillic,
+        words: &[
+            "и", "в", "не", "на", "аз", "че", "той", "с", "като", "това", "по", "но", "те", "ние", "вие",
+            "тя", "да", "се", "за", "от", "е", "са", "ще", "си", "ли", "какво", "здравей", "благодаря",
+            "много", "добре",
+        ],
+        trigrams: &[],
+        chars: "ъ",
+    },
+    Profile {
+        language: Language::Serbian,
+        script: Script::Cyrillic,
+        words: &[
+            "и", "у", "не", "на", "ја", "да", "је", "су", "се", "за", "од", "као", "али", "што", "шта", "он",
+            "она", "ми", "ви", "здраво", "хвала", "много", "добро",
+        ],
+        trigrams: &[],
+        chars: "ђјљњћџ",
+    },
+    Profile {
+        language: Language::Macedonian,
+        script: Script::Cyrillic,
+        words: &[
+            "и", "во", "не", "на", "јас", "дека", "тој", "со", "како", "ова", "но", "тие", "ние", "вие", "таа",
+            "да", "се", "за", "од", "е", "ќе", "што", "здраво", "благодарам", "многу",
+        ],
+        trigrams: &[],
+        chars: "ѓќѕјљњ",
+    },
+    Profile {
+        language: Language::Kazakh,
+        script: Script::Cyrillic,
+        words: &[
+            "және", "бұл", "мен", "сен", "ол", "біз", "сіз", "да", "де", "үшін", "бар", "жоқ", "емес", "сәлем",
+            "рахмет", "қалай", "жақсы",
+        ],
+        trigrams: &[],
+        chars: "әғқңөұүһі",
+    },
+    Profile {
+        language: Language::Mongolian,
+        script: Script::Cyrillic,
+        words: &[
+            "ба", "бол", "нь", "энэ", "тэр", "би", "чи", "бид", "та", "юу", "байна", "гэж", "байсан", "сайн",
+            "баярлалаа",
+        ],
+        trigrams: &[],
+        chars: "өү",
+    },
+    Profile {
+        language: Language::Hebrew,
+        script: Script::Hebrew,
+        words: &[
+            "של", "את", "על", "לא", "זה", "הוא", "היא", "אני", "אתה", "מה", "עם", "כי", "גם", "שלום", "יש",
+            "אין", "כל", "אבל", "תודה", "טוב",
+        ],
+        trigrams: &[],
+        chars: "",
+    },
+    Profile {
+        language: Language::Yiddish,
+        script: Script::Hebrew,
+        words: &[
+            "איז", "און", "דער", "די", "דאָס", "דאס", "ניט", "נישט", "מיט", "פֿון", "פון", "אַ", "ער", "זי", "איך",
+            "ביסט", "װאָס", "וואס", "גוט", "אױף",
+        ],
+        trigrams: &[],
+        chars: "ײױװ",
+    },
+    Profile {
+        language: Language::Arabic,
+        script: Script::Arabic,
+        words: &[
+            "في", "من", "على", "إلى", "الى", "أن", "هذا", "هذه", "التي", "الذي", "كان", "ما", "لا", "مع", "عن",
+            "هو", "هي", "أنا", "كيف", "مرحبا", "شكرا", "حالك",
+        ],
+        trigrams: &[],
+        chars: "ةىأإؤئي",
+    },
+    Profile {
+        language: Language::Farsi,
+        script: Script::Arabic,
+        words: &[
+            "و", "در", "به", "از", "که", "این", "را", "با", "است", "آن", "برای", "می", "ها", "من", "تو", "ما",
+            "شما", "چه", "چطور", "سلام", "خوب", "ممنون", "هست",
+        ],
+        trigrams: &[],
+        chars: "پچژگکی",
+    },
+    Profile {
+        language: Language::Urdu,
+        script: Script::Arabic,
+        words: &[
+            "کے", "کی", "کا", "ہے", "میں", "اور", "سے", "کو", "نے", "یہ", "وہ", "ہیں", "آپ", "کیا", "نہیں",
+            "شکریہ", "ہوں",
+        ],
+        trigrams: &[],
+        chars: "ٹڈڑںےہھ",
+    },
+    Profile {
+        language: Language::Pashto,
+        script: Script::Arabic,
+        words: &["د", "او", "په", "چې", "دا", "له", "ته", "یې", "کې", "دی", "سره", "زه", "ستاسو", "مننه"],
+        trigrams: &[],
+        chars: "ټډړښږځڅڼۍې",
+    },
+    Profile {
+        language: Language::Sindhi,
+        script: Script::Arabic,
+        words: &["۾", "جو", "جي", "آهي", "کي", "ته", "آهن", "هن", "مان"],
+        trigrams: &[],
+        chars: "ڄڃڇڌڍڎڏڙڦڪڻڳ",
+    },
+    Profile {
+        language: Language::Hindi,
+        script: Script::Devanagari,
+        words: &[
+            "है", "हैं", "और", "के", "की", "का", "में", "से", "को", "नहीं", "यह", "वह", "मेरा", "मेरी", "मैं", "हूँ",
+            "हूं", "था", "थी", "पर", "भी", "क्या", "आप", "तुम", "नमस्ते", "धन्यवाद",
+        ],
+        trigrams: &[],
+        chars: "",
+    },
+    Profile {
+        language: Language::Marathi,
+        script: Script::Devanagari,
+        words: &[
+            "आहे", "आहेत", "आणि", "च्या", "ला", "मी", "तू", "तो", "ती", "हे", "ते", "नाही", "माझे", "माझा",
+            "माझी", "नाव", "होते", "पण", "काय", "तुम्ही", "धन्यवाद",
+        ],
+        trigrams: &[],
+        chars: "ळ",
+    },
+    Profile {
+        language: Language::Nepali,
+        script: Script::Devanagari,
+        words: &[
+            "छ", "छन्", "र", "हो", "को", "मा", "ले", "लाई", "पनि", "म", "मेरो", "तपाईं", "तपाईंलाई", "यो",
+            "त्यो", "थियो", "हुन्छ", "छैन", "गर्छ",
+        ],
+        trigrams: &[],
+        chars: "",
+    },
+    Profile {
+        language: Language::Japanese,
+        script: Script::Kana,
+        words: &[],
+        trigrams: &[],
+        chars: "",
+    },
+    Profile {
+        language: Language::ChineseMandarin,
+        script: Script::Han,
+        words: &[],
+        trigrams: &[],
+        chars: "",
+    },
+    Profile {
+        language: Language::Korean,
+        script: Script::Hangul,
+        words: &[],
+        trigrams: &[],
+        chars: "",
+    },
+];
+
+/// Share of the letters the dominant script needs, mixed scripts are not detected.
+const MIN_SCRIPT_SHARE: f64 = 0.6;
+/// Weight of a matching trigram and of a distinctive character, relative to a matching word.
+const TRIGRAM_WEIGHT: f64 = 0.25;
+const CHAR_WEIGHT: f64 = 0.5;
+/// Evidence needed for a detection, about one frequent word.
+const MIN_SCORE: f64 = 1.0;
+/// Confidence below which the language is reported as undetected rather than guessed.
+const MIN_CONFIDENCE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DetectedLanguage {
+    pub(crate) language: SupportedLanguage,
+    /// Between 0 and 1: the share of letters in the script of the language for scripts used by a
+    /// single language, otherwise how far the language scored ahead of the runner-up
+    pub(crate) confidence: f64,
+}
+
+/// Identifies the language of `text`. The dominant script is determined first: scripts used by a
+/// single supported language decide on their own, Hiragana or Katakana mixed with Han characters
+/// denote Japanese. Languages sharing a script are scored by frequent words, character trigrams
+/// and distinctive characters. Returns `None` if the evidence is too weak or ambiguous.
+pub(crate) fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in text.chars().filter_map(script) {
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    let count = |script: Script| counts.iter().find(|(s, _)| *s == script).map(|(_, c)| *c).unwrap_or(0);
+    let letters: usize = counts.iter().map(|(_, c)| c).sum();
+    // Japanese mixes Han characters with Kana, so both count for the script which is present
+    let (dominant, dominant_count) = counts.iter()
+        .map(|(s, c)| match s {
+            Script::Kana | Script::Han if count(Script::Kana) > 0 => (Script::Kana, count(Script::Kana) + count(Script::Han)),
+            _ => (*s, *c),
+        })
+        .max_by_key(|(_, c)| *c)?;
+    let share = dominant_count as f64 / letters as f64;
+    if share < MIN_SCRIPT_SHARE {
+        return None;
+    }
+    let single = SCRIPT_LANGUAGES.iter().find(|(s, _)| *s == dominant).map(|(_, l)| *l)
+        .or(match dominant {
+            Script::Kana => Some(Language::Japanese),
+            Script::Han => Some(Language::ChineseMandarin),
+            _ => None,
+        });
+    if let Some(language) = single {
+        return Some(DetectedLanguage { language: SupportedLanguage::from_language(language)?, confidence: share });
+    }
+
+    let lowercase = text.to_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !is_word_char(c))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut scores: Vec<(Language, f64)> = PROFILES.iter()
+        .filter(|profile| profile.script == dominant)
+        .map(|profile| (profile.language, score(profile, &lowercase, &words)))
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let (language, best) = *scores.first()?;
+    let second = scores.get(1).map(|(_, score)| *score).unwrap_or(0.0);
+    let confidence = if best > 0.0 { 1.0 - second / best } else { 0.0 };
+    if best < MIN_SCORE || confidence < MIN_CONFIDENCE {
+        return None;
+    }
+    Some(DetectedLanguage { language: SupportedLanguage::from_language(language)?, confidence })
+}
+
+fn score(profile: &Profile, lowercase: &str, words: &[&str]) -> f64 {
+    let word_hits = words.iter().filter(|w| profile.words.contains(w)).count();
+    let trigram_hits = words.iter()
+        .map(|word| {
+            let padded: Vec<char> = format!("_{}_", word).chars().collect();
+            padded.windows(3)
+                .filter(|trigram| profile.trigrams.contains(&trigram.iter().collect::<String>().as_str()))
+                .count()
+        })
+        .sum::<usize>();
+    let char_hits = lowercase.chars().filter(|c| profile.chars.contains(*c)).count();
+    word_hits as f64 + trigram_hits as f64 * TRIGRAM_WEIGHT + char_hits as f64 * CHAR_WEIGHT
+}
+
+fn script(c: char) -> Option<Script> {
+    if c.is_ascii_alphabetic() || (c.is_alphabetic() && (('\u{00C0}'..='\u{024F}').contains(&c)
+        || ('\u{1E00}'..='\u{1EFF}').contains(&c))) {
+        return Some(Script::Latin);
+    }
+    if !is_word_char(c) {
+        return None;
+    }
+    SCRIPT_RANGES.iter()
+        .find(|(first, last, _)| (*first..=*last).contains(&c))
+        .map(|(_, _, script)| *script)
+}
+
+/// Letters, and the vowel signs and viramas of the Indic scripts which are not alphabetic in Unicode.
+fn is_word_char(c: char) -> bool {
+    c.is_alphabetic() || c == '\'' || (('\u{0900}'..='\u{0DFF}').contains(&c) && !matches!(c, '।' | '॥'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_languages() {
+        let cases: [(&str, Option<Language>); 37] = [
+            ("I have a dog and a cat.", Some(Language::English)),
+            ("The weather is nice today.", Some(Language::English)),
+            ("Je suis très content de te voir.", Some(Language::French)),
+            ("Bonjour, comment ça va?", Some(Language::French)),
+            ("Ich habe einen Hund und eine Katze.", Some(Language::German)),
+            ("Ik heb een hond en een kat.", Some(Language::Dutch)),
+            ("Tengo un perro y un gato.", Some(Language::Spanish)),
+            ("Eu tenho um cão e um gato.", Some(Language::Portuguese)),
+            ("Ho un cane e un gatto.", Some(Language::Italian)),
+            ("Mam psa i kota, to jest dobre.", Some(Language::Polish)),
+            ("Jag har en hund och en katt.", Some(Language::Swedish)),
+            ("Jeg har en hund og en kat, det er godt.", Some(Language::Danish)),
+            ("Minulla on koira ja kissa.", Some(Language::Finnish)),
+            ("Nekem van egy kutyám és egy macskám.", Some(Language::Hungarian)),
+            ("Am un câine și o pisică.", Some(Language::Romanian)),
+            ("Bir köpeğim ve bir kedim var.", Some(Language::Turkish)),
+            ("Saya punya anjing dan kucing.", Some(Language::Indonesian)),
+            ("Tôi có một con chó và một con mèo.", Some(Language::Vietnamese)),
+            ("Привет, как дела?", Some(Language::Russian)),
+            ("Привіт, як справи?", Some(Language::Ukrainian)),
+            ("Здравей, как си?", Some(Language::Bulgarian)),
+            ("今日はいい天気です。", Some(Language::Japanese)),
+            ("今天天气很好。", Some(Language::ChineseMandarin)),
+            ("안녕하세요, 잘 지내세요?", Some(Language::Korean)),
+            ("Καλημέρα, τι κάνεις;", Some(Language::Greek)),
+            ("שלום, מה שלומך?", Some(Language::Hebrew)),
+            ("مرحبا، كيف حالك؟", Some(Language::Arabic)),
+            ("سلام، حال شما چطور است؟", Some(Language::Farsi)),
+            ("آپ کیسے ہیں؟ میں ٹھیک ہوں۔", Some(Language::Urdu)),
+            ("मेरा नाम राम है।", Some(Language::Hindi)),
+            ("माझे नाव राम आहे.", Some(Language::Marathi)),
+            ("मेरो नाम राम हो।", Some(Language::Nepali)),
+            ("สวัสดีครับ", Some(Language::Thai)),
+            ("", None),
+            ("12345 !?", None),
+            ("ok", None),
+            ("Hello Привет", None),
+        ];
+        for (input, expected) in cases {
+            let detected = detect_language(input).map(|d| d.language.language());
+            assert_eq!(detected, expected, "input '{}'", input);
+        }
+    }
+}
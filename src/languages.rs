@@ -0,0 +1,89 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use rust_bert::m2m_100::{M2M100SourceLanguages, M2M100TargetLanguages};
+use rust_bert::pipelines::translation::Language;
+
+use crate::errors::ApiError;
+
+/// A language the translation backends can translate from or to. The Marian and MBart50 models
+/// cover a subset of the languages of M2M100, so the M2M100 languages are the full set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct SupportedLanguage(Language);
+
+impl SupportedLanguage {
+    pub(crate) const EN: SupportedLanguage = SupportedLanguage(Language::English);
+
+    /// All supported languages, in the order used by M2M100.
+    pub(crate) fn all() -> &'static [SupportedLanguage] {
+        static ALL: OnceLock<Vec<SupportedLanguage>> = OnceLock::new();
+        ALL.get_or_init(|| {
+            let mut all: Vec<SupportedLanguage> = Vec::new();
+            for language in M2M100SourceLanguages::M2M100_418M.iter()
+                .chain(M2M100TargetLanguages::M2M100_418M.iter()) {
+                let language = SupportedLanguage(*language);
+                if !all.contains(&language) {
+                    all.push(language);
+                }
+            }
+            all
+        })
+    }
+
+    pub(crate) fn from_language(language: Language) -> Option<SupportedLanguage> {
+        Self::all().iter().find(|l| l.0 == language).copied()
+    }
+
+    pub(crate) fn language(&self) -> Language {
+        self.0
+    }
+
+    /// ISO 639-1 code, or the ISO 639-3 code for languages without a two letter code.
+    pub(crate) fn code(&self) -> &'static str {
+        self.0.get_iso_639_1_code().unwrap_or(self.0.get_iso_639_3_code())
+    }
+
+    pub(crate) fn iso_639_3_code(&self) -> &'static str {
+        self.0.get_iso_639_3_code()
+    }
+
+    /// English name of the language, e.g. `Chinese Mandarin`.
+    pub(crate) fn name(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Languages this language can be translated into.
+    pub(crate) fn targets(&self) -> impl Iterator<Item=&'static SupportedLanguage> + '_ {
+        Self::all().iter().filter(move |target| *target != self)
+    }
+}
+
+impl FromStr for SupportedLanguage {
+    type Err = ();
+
+    /// Accepts ISO 639-1 and ISO 639-3 codes as well as English names, ignoring case.
+    fn from_str(input: &str) -> Result<SupportedLanguage, Self::Err> {
+        let input = input.trim().to_lowercase();
+        let without_spaces = input.replace(' ', "");
+        Self::all().iter()
+            .find(|l| l.code() == input
+                || l.iso_639_3_code() == input
+                || l.name().to_lowercase() == input
+                || format!("{:?}", l.0).to_lowercase() == without_spaces)
+            .copied()
+            .ok_or(())
+    }
+}
+
+/// Parses a language from a request. Unknown languages are rejected,
+/// unless `lenient` is set in which case they fall back to English.
+pub(crate) fn parse_language(code: &str, lenient: bool) -> Result<SupportedLanguage, ApiError> {
+    match SupportedLanguage::from_str(code) {
+        Ok(language) => Ok(language),
+        Err(_) if lenient => Ok(SupportedLanguage::EN),
+        Err(_) => Err(ApiError::UnsupportedLanguage {
+            value: code.to_string(),
+            accepted: SupportedLanguage::all().iter().map(|l| l.code().to_string()).collect(),
+        })
+    }
+}
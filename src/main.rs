@@ -1,20 +1,52 @@
 mod nlp;
 mod transport_structs;
 mod summarization_factory;
+mod model_cache;
+mod model_files;
+mod model_registry;
+mod preload;
+mod chunking;
+mod errors;
+mod extractive;
+mod jobs;
+mod glossary;
+mod inference;
+mod language_detection;
+mod markup;
+mod languages;
+mod segmentation;
+mod translation_factory;
 
 use std::future::Future;
+use std::ops::Range;
 use std::str::FromStr;
-use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
 use dotenv::dotenv;
 use ::config::Config;
 use actix_cors::Cors;
-use actix_web::{App, HttpResponse, HttpServer, Responder, web, get, post};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, Responder, web, delete, get, post};
+use actix_web::http::header::AUTHORIZATION;
+use rust_bert::pipelines::keywords_extraction::Keyword;
+use rust_bert::pipelines::sequence_classification::Label;
+use serde::Serialize;
 use chrono::{Datelike, Timelike, Utc};
-use rust_bert::RustBertError;
-use threadpool::ThreadPool;
-use crate::config::MainConfig;
-use crate::nlp::{dialogue, keyword_extraction, summarization, SupportedLanguage, translate_input, zero_shot_classification};
-use crate::transport_structs::{DialogueRequest, ErrorCodes, ExtractionKeyword, ExtractionResponse, Info, KeywordExtractionRequest, SummarizationRequest, SimpleTextResponse, TranslationRequest, TranslationResponse, ZeroShotRequest, ZeroShotResponse};
+use crate::config::{MainConfig, ModelFilesConfig};
+use crate::errors::ApiError;
+use crate::extractive::{SummarizationMode, DEFAULT_SUMMARY_SENTENCES, MAX_SUMMARY_SENTENCES};
+use crate::glossary::{Glossaries, Glossary};
+use crate::inference::InferencePool;
+use crate::jobs::{JobTask, Jobs, Progress};
+use crate::language_detection::{detect_language, DetectedLanguage};
+use crate::model_cache::{model_status, run_command, select};
+use crate::model_files::ModelFiles;
+use crate::model_registry::ModelRegistry;
+use crate::preload::{check_model_files, parse_preload_targets, preload_models};
+use crate::languages::{parse_language, SupportedLanguage};
+use crate::summarization_factory::SummarizationConfigFactory;
+use crate::translation_factory::TranslationDefaults;
+use crate::nlp::{classify, dialogue, extract_keywords, extract_sentences, extractive_summarization, keyword_extraction, reply, summarization, summarization_model, summarize, translate_batch, translate_input, translate_text, zero_shot_classification, ExtractedSentence, Translation, TranslationPlan};
+use crate::transport_structs::{BatchTranslationRequest, BatchTranslationResponse, DialogueRequest, ErrorCodes, ExtractionKeyword, ExtractiveSummaryResponse, ExtractionResponse, HealthResponse, Info, JobRequest, KeywordExtractionRequest, LanguageInfo, LanguagePairs, LanguagesResponse, ModelFilesResponse, SummarizationModelDescription, SummarizationModelsResponse, SummarizationRequest, SimpleTextResponse, SummarySentence, TextSpan, TranslationRequest, TranslationResponse, TranslationSegment, ZeroShotLabel, ZeroShotRequest, ZeroShotResponse};
 
 mod config {
     use serde::Deserialize;
@@ -22,128 +54,404 @@ mod config {
     #[derive(Debug, Default, Deserialize, Clone)]
     pub struct MainConfig {
         pub server_addr: String,
+        /// Comma separated list of models to load at startup,
+        /// e.g. `summarization:distilbart,translation:en-de,zero_shot,keywords,dialogue`
+        #[serde(default)]
+        pub preload_models: String,
+        /// Whether to run one inference on each preloaded model before reporting ready
+        #[serde(default = "default_warm_up_models")]
+        pub warm_up_models: bool,
+        /// Comma separated translation backends per language pair, e.g. `en-fr:m2m100,de-en:mbart50`
+        #[serde(default)]
+        pub translation_models: String,
+        /// Directory with the glossaries requests can refer to, one `<name>.tsv` file each
+        #[serde(default)]
+        pub glossaries: String,
+        /// Directory with local copies of the model files, one sub-directory per model
+        #[serde(default)]
+        pub model_dir: String,
+        /// Whether model files missing from `model_dir` may be downloaded
+        #[serde(default = "default_allow_remote_models")]
+        pub allow_remote_models: bool,
+        /// Number of threads running the models, shared by all pipelines and jobs
+        #[serde(default = "default_inference_threads")]
+        pub inference_threads: usize,
+        /// Maximum number of inference tasks waiting for a thread, further requests are rejected
+        #[serde(default = "default_max_inference_queue")]
+        pub max_inference_queue: usize,
+        /// Milliseconds requests wait for their result unless they set `timeout_ms`
+        #[serde(default = "default_inference_timeout_ms")]
+        pub inference_timeout_ms: u64,
+        /// Maximum number of jobs waiting to run, further jobs are rejected
+        #[serde(default = "default_max_queued_jobs")]
+        pub max_queued_jobs: usize,
+        /// Seconds finished jobs are kept for clients to fetch their result
+        #[serde(default = "default_job_retention_secs")]
+        pub job_retention_secs: u64,
+        /// Bearer token required by the `/admin` endpoints, which are disabled if it is empty
+        #[serde(default)]
+        pub admin_token: String,
+    }
+
+    /// The settings of `MainConfig` the `models` subcommand needs, read without the server-only ones.
+    #[derive(Debug, Default, Deserialize, Clone)]
+    pub struct ModelFilesConfig {
+        #[serde(default)]
+        pub model_dir: String,
+        #[serde(default = "default_allow_remote_models")]
+        pub allow_remote_models: bool,
+    }
+
+    fn default_inference_threads() -> usize {
+        4
+    }
+
+    fn default_max_inference_queue() -> usize {
+        64
+    }
+
+    fn default_inference_timeout_ms() -> u64 {
+        300_000
+    }
+
+    fn default_max_queued_jobs() -> usize {
+        16
+    }
+
+    fn default_job_retention_secs() -> u64 {
+        3600
+    }
+
+    fn default_allow_remote_models() -> bool {
+        true
+    }
+
+    fn default_warm_up_models() -> bool {
+        true
     }
 }
 
 #[post("/translate")]
-async fn translate(info: web::Json<TranslationRequest>) -> impl Responder {
-    let orig_text = &info.orig_text;
-    let souce_language = &info.language;
-    let supported_language_res = SupportedLanguage::from_str(souce_language.as_str());
-    let supported_language = supported_language_res.unwrap_or(SupportedLanguage::En);
-    // If the source language is not found then default to English, otherwise return none and also defaults to English
-    let source_language = match &info.source_language {
-        Some(l) => SupportedLanguage::from_str(l.as_str()).unwrap_or(SupportedLanguage::En),
-        None => SupportedLanguage::En
-    };
-    let res = translate_input(
-        supported_language,
-        source_language,
-        info.orig_text.clone());
-    match res.await {
-        Ok(s) => {
-            HttpResponse::Ok().json(TranslationResponse {
-                orig_text: orig_text.clone(),
-                translation: s
-            })
+async fn translate(info: web::Json<TranslationRequest>, pool: web::Data<InferencePool>,
+                   registry: web::Data<ModelRegistry>,
+                   translation_defaults: web::Data<TranslationDefaults>,
+                   glossaries: web::Data<Glossaries>) -> Result<HttpResponse, ApiError> {
+    let plan = plan_translation(&info, &translation_defaults, &glossaries)?;
+    let translation = pool.within(info.timeout_ms,
+        translate_input(plan.clone(), info.orig_text.clone(), info.format, &pool, registry)).await?;
+    Ok(HttpResponse::Ok().json(translation_response(&info.orig_text, &plan, translation)))
+}
+
+fn plan_translation(info: &TranslationRequest, translation_defaults: &TranslationDefaults, glossaries: &Glossaries)
+    -> Result<TranslationPlan, ApiError> {
+    let target = parse_language(&info.language, info.lenient)?;
+    let (source, detected) = source_language(&info.source_language, &info.orig_text, info.lenient)?;
+    let backend = translation_defaults.resolve(
+        info.model.as_deref(), source.language(), target.language(), info.lenient)?;
+    let glossary = glossaries.resolve(&info.glossaries, Glossary::new(&info.glossary, &info.do_not_translate))?;
+    Ok(TranslationPlan { target, source, detected, backend, glossary })
+}
+
+fn translation_response(orig_text: &str, plan: &TranslationPlan, translation: Translation) -> TranslationResponse {
+    let segments = translation.segments.into_iter().enumerate()
+        .map(|(i, segment)| TranslationSegment {
+            index: i,
+            source: orig_text[segment.source.clone()].to_string(),
+            translation: segment.translation,
+            span: TextSpan::from_bytes(orig_text, segment.source.start, segment.source.end)
+        })
+        .collect();
+    TranslationResponse {
+        orig_text: orig_text.to_string(),
+        translation: translation.text,
+        model: plan.backend.name().to_string(),
+        detected_language: plan.detected.map(|d| d.language.code().to_string()),
+        detection_confidence: plan.detected.map(|d| d.confidence),
+        segments
+    }
+}
+
+#[post("/translate/batch")]
+async fn translate_batch_request(info: web::Json<BatchTranslationRequest>, pool: web::Data<InferencePool>,
+                                 registry: web::Data<ModelRegistry>,
+                                 translation_defaults: web::Data<TranslationDefaults>,
+                                 glossaries: web::Data<Glossaries>) -> Result<HttpResponse, ApiError> {
+    if info.texts.is_empty() || info.languages.is_empty() {
+        return Err(ApiError::Validation(String::from("Both texts and languages must not be empty")));
+    }
+    let (source_language, detected) = source_language(&info.source_language, &info.texts.join("\n"), info.lenient)?;
+    let mut targets = Vec::new();
+    for language in &info.languages {
+        let target = parse_language(language, info.lenient)?;
+        let backend = translation_defaults.resolve(
+            info.model.as_deref(), source_language.language(), target.language(), info.lenient)?;
+        if !targets.iter().any(|(t, _)| *t == target) {
+            targets.push((target, backend));
         }
-        Err(e) => {
-            HttpResponse::InternalServerError().json(TranslationResponse {
-                orig_text: orig_text.clone(),
-                translation: format!("{:?}", e)
-            })
+    }
+    let glossary = glossaries.resolve(&info.glossaries, Glossary::new(&info.glossary, &info.do_not_translate))?;
+    let results = pool.within(info.timeout_ms, translate_batch(
+        targets, source_language, info.texts.clone(), info.format, glossary, &pool, registry)).await?;
+    Ok(HttpResponse::Ok().json(BatchTranslationResponse {
+        texts: info.texts.clone(),
+        models: results.iter().map(|r| (r.target.code().to_string(), r.model.clone())).collect(),
+        translations: results.into_iter().map(|r| (r.target.code().to_string(), r.translations)).collect(),
+        detected_language: detected.map(|d| d.language.code().to_string()),
+        detection_confidence: detected.map(|d| d.confidence)
+    }))
+}
+
+/// Parses the source language of a translation request. If none is given it is detected from
+/// the text. If detection fails the request is rejected, or English is assumed when lenient.
+fn source_language(source_language: &Option<String>, text: &str, lenient: bool)
+    -> Result<(SupportedLanguage, Option<DetectedLanguage>), ApiError> {
+    match source_language {
+        Some(l) => Ok((parse_language(l, lenient)?, None)),
+        None => match detect_language(text) {
+            Some(detected) => Ok((detected.language, Some(detected))),
+            None if lenient => Ok((SupportedLanguage::EN, None)),
+            None => Err(ApiError::UndetectedLanguage(String::from(
+                "The source language could not be detected, set source_language")))
         }
     }
 }
 
+#[get("/translate/languages")]
+async fn translation_languages() -> impl Responder {
+    let languages = SupportedLanguage::all().iter()
+        .map(|l| LanguageInfo {
+            code: l.code().to_string(),
+            iso_639_3: l.iso_639_3_code().to_string(),
+            name: l.name()
+        })
+        .collect();
+    let pairs = SupportedLanguage::all().iter()
+        .map(|source| LanguagePairs {
+            source: source.code().to_string(),
+            targets: source.targets().map(|target| target.code().to_string()).collect()
+        })
+        .collect();
+    HttpResponse::Ok().json(LanguagesResponse { languages, pairs })
+}
+
 #[post("/zero_shot")]
-async fn zero_shot_classification_service(request: web::Json<ZeroShotRequest>) -> impl Responder {
-    let labels = &request.labels.clone()
-        .unwrap_or(vec!["politics", "public health", "economics", "sports", "arts"].iter()
-            .map(|s| s.to_string()).collect());
-    let res = zero_shot_classification(
-        request.orig_text.clone(),
+async fn zero_shot_classification_service(request: web::Json<ZeroShotRequest>, pool: web::Data<InferencePool>,
+                                          registry: web::Data<ModelRegistry>) -> Result<HttpResponse, ApiError> {
+    let labels = &zero_shot_labels(&request);
+    let orig_text = &request.orig_text;
+    let (ranges, responses) = pool.within(request.timeout_ms, zero_shot_classification(
+        orig_text.clone(),
         request.split,
-        labels
-    );
-    match res.await {
-        Ok(vecs) => {
-            let (sentences, responses) = vecs;
-            HttpResponse::Ok().json(ZeroShotResponse {
-                sentences,
-                responses,
-                status: String::from(ErrorCodes::STATUS_OK)
-            })
-        }
-        Err(_) => {
-            HttpResponse::InternalServerError().json(ZeroShotResponse {
-                sentences: vec!(request.orig_text.clone()),
-                responses: vec!(),
-                status: String::from(ErrorCodes::STATUS_FAILED)
-            })
-        }
+        labels,
+        &pool,
+        registry
+    )).await?;
+    Ok(HttpResponse::Ok().json(zero_shot_response(orig_text, ranges, responses)))
+}
+
+fn zero_shot_labels(request: &ZeroShotRequest) -> Vec<String> {
+    request.labels.clone()
+        .unwrap_or(["politics", "public health", "economics", "sports", "arts"].iter()
+            .map(|s| s.to_string()).collect())
+}
+
+fn zero_shot_response(orig_text: &str, ranges: Vec<Range<usize>>, responses: Vec<Vec<Label>>) -> ZeroShotResponse {
+    let spans: Vec<TextSpan> = ranges.iter().map(|r| TextSpan::from_bytes(orig_text, r.start, r.end)).collect();
+    let responses = responses.into_iter()
+        .map(|labels| labels.into_iter()
+            .map(|label| ZeroShotLabel { span: spans[label.sentence], label }).collect())
+        .collect();
+    ZeroShotResponse {
+        sentences: ranges.into_iter().map(|r| orig_text[r].to_string()).collect(),
+        spans,
+        responses,
+        status: String::from(ErrorCodes::STATUS_OK)
     }
 }
 
 #[post("/keyword_extraction")]
-async fn keyword_extraction_service(request: web::Json<KeywordExtractionRequest>, pool: web::Data<ThreadPool>) -> impl Responder {
-    let res = keyword_extraction(request, pool);
-    match res.await {
-        Ok(vec) => {
-            let keyword_res = vec.iter()
-                .map(|child| child.iter()
-                    .map(|k| ExtractionKeyword { text: k.text.clone(), score: k.score}).collect()).collect();
-            let extraction_keyword = ExtractionResponse{
-                results: keyword_res,
-                status: String::from(ErrorCodes::STATUS_OK)
-            };
-            HttpResponse::Ok().json(extraction_keyword)
-        }
-        Err(_) => {
-            HttpResponse::InternalServerError().json(ExtractionResponse {
-                results: vec![],
-                status: String::from(ErrorCodes::STATUS_FAILED)
-            })
-        }
+async fn keyword_extraction_service(request: web::Json<KeywordExtractionRequest>, pool: web::Data<InferencePool>,
+                                    registry: web::Data<ModelRegistry>) -> Result<HttpResponse, ApiError> {
+    let orig_text = request.orig_text.clone();
+    let timeout_ms = request.timeout_ms;
+    let (ranges, vec) = pool.within(timeout_ms, keyword_extraction(request, &pool, registry)).await?;
+    Ok(HttpResponse::Ok().json(extraction_response(&orig_text, ranges, &vec)))
+}
+
+fn extraction_response(orig_text: &str, ranges: Vec<Range<usize>>, keywords: &[Vec<Keyword>]) -> ExtractionResponse {
+    let keyword_res = keywords.iter().zip(ranges)
+        .map(|(child, range)| child.iter()
+            .map(|k| ExtractionKeyword {
+                text: k.text.clone(),
+                score: k.score,
+                offsets: k.offsets.iter()
+                    .map(|o| TextSpan::from_bytes(orig_text, range.start + o.begin as usize, range.start + o.end as usize))
+                    .collect()
+            }).collect()).collect();
+    ExtractionResponse {
+        results: keyword_res,
+        status: String::from(ErrorCodes::STATUS_OK)
     }
 }
 
 #[post("/summarization")]
-async fn summarization_service(request: web::Json<SummarizationRequest>, pool: web::Data<ThreadPool>) -> impl Responder {
+async fn summarization_service(request: web::Json<SummarizationRequest>, pool: web::Data<InferencePool>,
+                               registry: web::Data<ModelRegistry>) -> Result<HttpResponse, ApiError> {
+    if request.mode == SummarizationMode::Extractive {
+        return extractive_summarization_service(request, pool, registry).await;
+    }
     let model_option = &request.model;
     let res = summarization(
-        request.orig_text.clone(), model_option, pool
+        request.orig_text.clone(), model_option, request.strategy, request.generation.clone(), request.lenient, &pool,
+        registry
     );
-    process_simple_text_response(res).await
+    process_simple_text_response(pool.within(request.timeout_ms, res)).await
 }
 
-fn create_simple_text_error(e: RustBertError) -> HttpResponse {
-    HttpResponse::InternalServerError().json(SimpleTextResponse {
-        text: format!("{:?}", e),
-        status: ErrorCodes::STATUS_FAILED.to_string()
-    })
+#[get("/summarization/models")]
+async fn summarization_models() -> impl Responder {
+    let default_model = SummarizationConfigFactory::default_model().name;
+    let models = SummarizationConfigFactory::models().iter()
+        .map(|m| SummarizationModelDescription {
+            name: m.name.to_string(),
+            description: m.description.to_string(),
+            max_input_tokens: m.max_input_tokens,
+            language: m.language.get_iso_639_1_code().unwrap_or(m.language.get_iso_639_3_code()).to_string(),
+            default: m.name == default_model
+        })
+        .collect();
+    HttpResponse::Ok().json(SummarizationModelsResponse { models })
+}
+
+async fn extractive_summarization_service(request: web::Json<SummarizationRequest>, pool: web::Data<InferencePool>,
+                                          registry: web::Data<ModelRegistry>) -> Result<HttpResponse, ApiError> {
+    let count = extractive_sentence_count(&request)?;
+    let orig_text = request.orig_text.clone();
+    let extracted = pool.within(request.timeout_ms,
+        extractive_summarization(orig_text.clone(), count, &pool, registry)).await?;
+    Ok(HttpResponse::Ok().json(extractive_summary_response(&orig_text, extracted)))
+}
+
+fn extractive_sentence_count(request: &SummarizationRequest) -> Result<usize, ApiError> {
+    let count = request.sentences.unwrap_or(DEFAULT_SUMMARY_SENTENCES);
+    if !(1..=MAX_SUMMARY_SENTENCES).contains(&count) {
+        return Err(ApiError::Validation(format!("sentences must be between 1 and {}", MAX_SUMMARY_SENTENCES)));
+    }
+    Ok(count)
+}
+
+fn extractive_summary_response(orig_text: &str, extracted: Vec<ExtractedSentence>) -> ExtractiveSummaryResponse {
+    let sentences: Vec<SummarySentence> = extracted.into_iter()
+        .map(|s| SummarySentence {
+            index: s.index,
+            text: orig_text[s.range.clone()].to_string(),
+            score: s.score,
+            span: TextSpan::from_bytes(orig_text, s.range.start, s.range.end)
+        })
+        .collect();
+    ExtractiveSummaryResponse {
+        text: sentences.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        sentences,
+        status: ErrorCodes::STATUS_OK.to_string()
+    }
 }
 
 #[post("/dialogue")]
-async fn dialogue_service(request: web::Json<DialogueRequest>, pool: web::Data<ThreadPool>) -> impl Responder {
+async fn dialogue_service(request: web::Json<DialogueRequest>, pool: web::Data<InferencePool>,
+                          registry: web::Data<ModelRegistry>) -> Result<HttpResponse, ApiError> {
     let res = dialogue(
-        request.question.clone(), pool
+        request.question.clone(), &pool, registry
     );
-    return process_simple_text_response(res).await;
+    process_simple_text_response(pool.within(request.timeout_ms, res)).await
+}
+
+async fn process_simple_text_response(res: impl Future<Output=Result<String, ApiError>>) -> Result<HttpResponse, ApiError> {
+    let text = res.await?;
+    Ok(HttpResponse::Ok().json(simple_text_response(text)))
+}
+
+fn simple_text_response(text: String) -> SimpleTextResponse {
+    SimpleTextResponse {
+        text,
+        status: ErrorCodes::STATUS_OK.to_string()
+    }
+}
+
+#[post("/jobs")]
+async fn create_job(request: web::Json<JobRequest>, jobs: web::Data<Jobs>, pool: web::Data<InferencePool>,
+                    registry: web::Data<ModelRegistry>, translation_defaults: web::Data<TranslationDefaults>,
+                    glossaries: web::Data<Glossaries>) -> Result<HttpResponse, ApiError> {
+    let request = request.into_inner();
+    let name = request.task();
+    let task = job_task(request, &translation_defaults, &glossaries)?;
+    let job = jobs.add(name)?;
+    let id = job.id.clone();
+    let queued_jobs = jobs.clone().into_inner();
+    if let Err(e) = pool.execute(move || queued_jobs.run(&id, task, &registry)) {
+        jobs.remove(&job.id);
+        return Err(e);
+    }
+    Ok(HttpResponse::Accepted()
+        .insert_header(("Location", format!("/jobs/{}", job.id)))
+        .json(job))
 }
 
-async fn process_simple_text_response(res: impl Future<Output=Result<String, RustBertError>>) -> HttpResponse {
-    match res.await {
-        Ok(s) => {
-            HttpResponse::Ok().json(SimpleTextResponse {
-                text: s,
-                status: ErrorCodes::STATUS_OK.to_string()
+/// Validates the payload of a job like its synchronous endpoint does and returns the work to run on the pool.
+fn job_task(request: JobRequest, translation_defaults: &TranslationDefaults, glossaries: &Glossaries)
+    -> Result<JobTask, ApiError> {
+    Ok(match request {
+        JobRequest::Summarize(request) if request.mode == SummarizationMode::Extractive => {
+            let count = extractive_sentence_count(&request)?;
+            Box::new(move |registry: &ModelRegistry, _: Progress| {
+                let extracted = extract_sentences(&request.orig_text, count, registry)?;
+                json_value(extractive_summary_response(&request.orig_text, extracted))
             })
         }
-        Err(e) => {
-            create_simple_text_error(e)
+        JobRequest::Summarize(request) => {
+            request.generation.validate()?;
+            let model = summarization_model(&request.model, request.lenient)?;
+            Box::new(move |registry: &ModelRegistry, progress: Progress| {
+                let text = summarize(&request.orig_text, model, request.strategy, &request.generation, registry,
+                                     progress)?;
+                json_value(simple_text_response(text))
+            })
         }
-    }
+        JobRequest::Translate(request) => {
+            let plan = plan_translation(&request, translation_defaults, glossaries)?;
+            Box::new(move |registry: &ModelRegistry, _: Progress| {
+                let translation = translate_text(&plan, &request.orig_text, request.format, registry)?;
+                json_value(translation_response(&request.orig_text, &plan, translation))
+            })
+        }
+        JobRequest::ZeroShot(request) => {
+            let labels = zero_shot_labels(&request);
+            Box::new(move |registry: &ModelRegistry, _: Progress| {
+                let (ranges, responses) = classify(&request.orig_text, request.split, &labels, registry)?;
+                json_value(zero_shot_response(&request.orig_text, ranges, responses))
+            })
+        }
+        JobRequest::Keywords(request) => Box::new(move |registry: &ModelRegistry, _: Progress| {
+            let (ranges, keywords) = extract_keywords(&request, registry)?;
+            json_value(extraction_response(&request.orig_text, ranges, &keywords))
+        }),
+        JobRequest::Dialogue(request) => Box::new(move |registry: &ModelRegistry, _: Progress| {
+            json_value(simple_text_response(reply(&request.question, registry)?))
+        }),
+    })
+}
+
+fn json_value(response: impl Serialize) -> Result<serde_json::Value, ApiError> {
+    serde_json::to_value(response).map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+#[get("/jobs/{id}")]
+async fn job_status(id: web::Path<String>, jobs: web::Data<Jobs>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(jobs.status(&id)?))
+}
+
+#[delete("/jobs/{id}")]
+async fn cancel_job(id: web::Path<String>, jobs: web::Data<Jobs>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(jobs.cancel(&id)?))
 }
 
 
@@ -156,46 +464,152 @@ fn create_timestamp() -> String {
 }
 
 #[get("/")]
-async fn index(config: web::Data<MainConfig>, pool: web::Data<ThreadPool>) -> impl Responder {
-    let timestamp = create_timestamp();
-    let (tx, rx) = channel();
-    pool.execute(move || {
-        tx.send("Welcome to NLP API!".to_string()).expect("channel will be there waiting for the pool")
-    });
-    let msg = rx.recv().unwrap();
+async fn index(config: web::Data<MainConfig>, registry: web::Data<ModelRegistry>) -> impl Responder {
     HttpResponse::Ok().json(Info {
-        message: msg,
+        message: "Welcome to NLP API!".to_string(),
         server_address: config.server_addr.clone(),
-        timestamp,
+        timestamp: create_timestamp(),
+        ready: registry.is_ready(),
+    })
+}
+
+#[get("/health/live")]
+async fn health_live() -> impl Responder {
+    HttpResponse::Ok().json(HealthResponse {
+        status: String::from("alive"),
+        timestamp: create_timestamp(),
+        pipelines: None,
+    })
+}
+
+#[get("/health/ready")]
+async fn health_ready(registry: web::Data<ModelRegistry>) -> impl Responder {
+    let ready = registry.is_ready();
+    let mut response = if ready { HttpResponse::Ok() } else { HttpResponse::ServiceUnavailable() };
+    response.json(HealthResponse {
+        status: String::from(if ready { "ready" } else { "not_ready" }),
+        timestamp: create_timestamp(),
+        pipelines: Some(registry.model_statuses()),
     })
 }
 
+#[get("/admin/models")]
+async fn admin_models(request: HttpRequest, config: web::Data<MainConfig>,
+                      model_files: web::Data<ModelFiles>) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&request, &config)?;
+    model_files_response(model_files, false).await
+}
+
+#[get("/admin/models/verify")]
+async fn admin_models_verify(request: HttpRequest, config: web::Data<MainConfig>,
+                             model_files: web::Data<ModelFiles>) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&request, &config)?;
+    model_files_response(model_files, true).await
+}
+
+/// The admin endpoints reveal file system paths and read whole model files, so they require `admin_token`
+/// and are not served at all without one.
+fn check_admin_token(request: &HttpRequest, config: &MainConfig) -> Result<(), ApiError> {
+    if config.admin_token.is_empty() {
+        return Err(ApiError::NotFound(String::from("The admin endpoints are disabled, set admin_token to enable them")));
+    }
+    let token = request.headers().get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if token != Some(config.admin_token.as_str()) {
+        return Err(ApiError::Unauthorized(String::from("A valid admin token is required")));
+    }
+    Ok(())
+}
+
+/// Reports the files of every model; checksums are computed on a blocking thread as they read whole files.
+async fn model_files_response(model_files: web::Data<ModelFiles>, checksums: bool) -> Result<HttpResponse, ApiError> {
+    let models = web::block(move || select(None, None).iter()
+        .map(|model| model_status(&model_files, model, checksums))
+        .collect::<std::io::Result<Vec<_>>>())
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(ModelFilesResponse { models }))
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
 
     let config_ = Config::builder()
+        .add_source(::config::File::with_name("config").required(false))
         .add_source(::config::Environment::default())
         .build()
         .unwrap();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|a| a.as_str()) == Some("models") {
+        let config: ModelFilesConfig = config_.try_deserialize().unwrap();
+        let model_files = ModelFiles::new(&config.model_dir, config.allow_remote_models)
+            .expect("Invalid model_dir configuration");
+        return run_command(&args[1..], &model_files);
+    }
+
     let config: MainConfig = config_.try_deserialize().unwrap();
 
     let server_addr = config.server_addr.clone();
+    let model_files = ModelFiles::new(&config.model_dir, config.allow_remote_models)
+        .expect("Invalid model_dir configuration");
+    let registry = web::Data::new(ModelRegistry::new(model_files.clone()));
+    let model_files = web::Data::new(model_files);
+    let pool = web::Data::new(InferencePool::new(config.inference_threads, config.max_inference_queue,
+                                                 Duration::from_millis(config.inference_timeout_ms))
+        .expect("Invalid inference configuration"));
+    let jobs = web::Data::new(Jobs::new(config.max_queued_jobs, Duration::from_secs(config.job_retention_secs)));
+
+    let translation_defaults = web::Data::new(TranslationDefaults::from_str(&config.translation_models)
+        .expect("Invalid translation_models configuration"));
+
+    let glossaries = web::Data::new(Glossaries::load(&config.glossaries)
+        .expect("Invalid glossaries configuration"));
+
+    let preload_targets = parse_preload_targets(&config.preload_models)
+        .expect("Invalid preload_models configuration");
+    check_model_files(&model_files, &preload_targets, &translation_defaults)
+        .expect("Missing files of preloaded models");
+    let warm_up = config.warm_up_models;
+    let preload_registry = registry.clone();
+    let preload_translation_defaults = translation_defaults.clone();
+    thread::spawn(move || {
+        if let Err(e) = preload_models(&preload_registry, &preload_targets, &preload_translation_defaults, warm_up) {
+            println!("Failed to preload models: {:?}", e);
+        }
+    });
 
     HttpServer::new(move || {
-        let num_workers = 4;
-        let pool = ThreadPool::new(num_workers);
         App::new()
             .wrap(Cors::permissive())
             .app_data(web::Data::new(config.clone()))
-            .app_data(web::Data::new(pool))
+            .app_data(pool.clone())
+            .app_data(registry.clone())
+            .app_data(translation_defaults.clone())
+            .app_data(glossaries.clone())
+            .app_data(model_files.clone())
+            .app_data(jobs.clone())
+            .app_data(web::JsonConfig::default()
+                .error_handler(|err, _| ApiError::Validation(err.to_string()).into()))
             .service(index)
+            .service(health_live)
+            .service(health_ready)
             .service(summarization_service)
+            .service(summarization_models)
             .service(translate)
+            .service(translate_batch_request)
+            .service(translation_languages)
             .service(zero_shot_classification_service)
             .service(keyword_extraction_service)
             .service(dialogue_service)
+            .service(admin_models)
+            .service(admin_models_verify)
+            .service(create_job)
+            .service(job_status)
+            .service(cancel_job)
     })
         .bind(server_addr)?
         .run()
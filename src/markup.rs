@@ -0,0 +1,489 @@
+use std::ops::Range;
+
+use serde::Deserialize;
+
+/// Elements whose content is never translated.
+const RAW_ELEMENTS: [&str; 6] = ["script", "style", "code", "pre", "kbd", "samp"];
+
+/// Prefixes of URLs left untouched inside text.
+const URL_PREFIXES: [&str; 4] = ["http://", "https://", "www.", "mailto:"];
+
+/// Format of a text to translate.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TextFormat {
+    #[default]
+    Text,
+    Html,
+    Markdown,
+}
+
+/// Inline elements, which stay inside the sentence around them. Any other tag ends a block of text.
+const INLINE_ELEMENTS: [&str; 28] = [
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "del", "dfn", "em", "font", "i", "img", "ins", "kbd",
+    "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// A block of translatable text, like a paragraph or a table cell, with the inline markup inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TextBlock {
+    pub(crate) range: Range<usize>,
+    /// Byte ranges of tags, emphasis markers, inline code, link targets and URLs inside `range`, in order
+    pub(crate) markup: Vec<Range<usize>>,
+}
+
+impl TextBlock {
+    fn new(start: usize) -> Self {
+        TextBlock { range: start..start, markup: Vec::new() }
+    }
+
+    fn add_markup(&mut self, range: Range<usize>) {
+        match self.markup.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => self.markup.push(range),
+        }
+    }
+
+    /// The text of the block with its markup replaced by spaces, keeping the byte offsets, so that
+    /// sentence boundaries are found in the text only.
+    pub(crate) fn masked(&self, text: &str) -> String {
+        let mut masked = String::with_capacity(self.range.len());
+        let mut last = self.range.start;
+        for markup in &self.markup {
+            masked.push_str(&text[last..markup.start]);
+            masked.extend(std::iter::repeat_n(' ', markup.len()));
+            last = markup.end;
+        }
+        masked.push_str(&text[last..self.range.end]);
+        masked
+    }
+
+    /// The text of `segment` with the markup inside it replaced by placeholders, like glossary terms,
+    /// together with the markup in order.
+    pub(crate) fn protect<'a>(&self, text: &'a str, segment: Range<usize>) -> (String, Vec<&'a str>) {
+        let mut protected = String::with_capacity(segment.len());
+        let mut markup = Vec::new();
+        let mut last = segment.start;
+        for range in self.markup.iter().filter(|m| m.start >= segment.start && m.end <= segment.end) {
+            protected.push_str(&text[last..range.start]);
+            protected.push_str(&placeholder(markup.len()));
+            markup.push(&text[range.clone()]);
+            last = range.end;
+        }
+        protected.push_str(&text[last..segment.end]);
+        (protected, markup)
+    }
+}
+
+/// Replaces the placeholders of a translation with the markup they stand for. If the model dropped or
+/// mangled any placeholder, the remaining ones are removed and all the markup is appended in its original
+/// order instead, so that tags stay balanced and nothing is lost.
+pub(crate) fn restore_markup(translation: &str, markup: &[&str]) -> String {
+    let placeholders: Vec<String> = (0..markup.len()).map(placeholder).collect();
+    if placeholders.iter().all(|p| translation.contains(p.as_str())) {
+        let mut restored = translation.to_string();
+        for (placeholder, markup) in placeholders.iter().zip(markup) {
+            let position = restored.find(placeholder.as_str()).unwrap_or_default();
+            restored.replace_range(position..position + placeholder.len(), markup);
+        }
+        return restored;
+    }
+    println!("Placeholders missing in translation '{}', appending the markup", translation);
+    let mut restored = placeholders.iter().fold(translation.to_string(), |t, p| t.replace(p.as_str(), ""));
+    restored.extend(markup.iter().copied());
+    restored
+}
+
+fn placeholder(index: usize) -> String {
+    format!("ZXM{}MXZ", index)
+}
+
+/// Blocks of translatable text of `text`. Plain text is translated as a whole; for HTML blocks end at
+/// block-level elements and for Markdown at paragraphs, headings, list items and table cells, leaving
+/// tags, attributes, code and URLs untouched.
+pub(crate) fn translatable_blocks(text: &str, format: TextFormat) -> Vec<TextBlock> {
+    let mut blocks = Vec::new();
+    match format {
+        TextFormat::Text => blocks.push(TextBlock { range: 0..text.len(), markup: Vec::new() }),
+        TextFormat::Html => html_blocks(text, &mut blocks),
+        TextFormat::Markdown => markdown_blocks(text, &mut blocks),
+    }
+    blocks
+}
+
+fn html_blocks(text: &str, blocks: &mut Vec<TextBlock>) {
+    let bytes = text.as_bytes();
+    let mut block = TextBlock::new(0);
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        let Some(tag_end) = html_tag_end(text, i) else {
+            i += 1;
+            continue;
+        };
+        let mut end = tag_end;
+        if let Some(name) = html_tag_name(&text[i..tag_end]).filter(|n| RAW_ELEMENTS.contains(&n.as_str())) {
+            let closing = format!("</{}", name);
+            let closing_start = text.as_bytes()[tag_end..].windows(closing.len())
+                .position(|w| w.eq_ignore_ascii_case(closing.as_bytes()))
+                .map(|p| tag_end + p);
+            end = closing_start.unwrap_or(text.len());
+            // Inline code is kept with its closing tag, the closing tag of other raw elements ends the block next
+            if let Some(closing_start) = closing_start.filter(|_| INLINE_ELEMENTS.contains(&name.as_str())) {
+                end = html_tag_end(text, closing_start).unwrap_or(text.len());
+            }
+        }
+        if INLINE_ELEMENTS.contains(&html_element(&text[i..tag_end]).as_str()) {
+            block.add_markup(i..end);
+        } else {
+            push_block(text, block, i, blocks);
+            block = TextBlock::new(end);
+        }
+        i = end;
+    }
+    push_block(text, block, text.len(), blocks);
+}
+
+/// End of the tag, comment or declaration starting at `start`, or `None` if `<` does not start one.
+fn html_tag_end(text: &str, start: usize) -> Option<usize> {
+    if start >= text.len() {
+        return None;
+    }
+    let rest = &text[start..];
+    if rest.starts_with("<!--") {
+        return Some(rest.find("-->").map(|p| start + p + 3).unwrap_or(text.len()));
+    }
+    match rest[1..].chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '/' || c == '!' || c == '?' => {}
+        _ => return None,
+    }
+    let mut quote = None;
+    for (p, c) in rest.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => return Some(start + p + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lowercase name of an opening tag, e.g. `pre` for `<pre class="x">`.
+fn html_tag_name(tag: &str) -> Option<String> {
+    let tag = &tag[1..];
+    let name: String = tag.chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+    if name.is_empty() || tag.ends_with("/>") { None } else { Some(name.to_lowercase()) }
+}
+
+/// Lowercase element name of an opening or closing tag, e.g. `a` for `<a href="x">` and `</a>`.
+/// Empty for comments and declarations.
+fn html_element(tag: &str) -> String {
+    tag[1..].trim_start_matches('/').chars().take_while(|c| c.is_ascii_alphanumeric()).collect::<String>()
+        .to_lowercase()
+}
+
+fn markdown_blocks(text: &str, blocks: &mut Vec<TextBlock>) {
+    let mut fence: Option<&str> = None;
+    let mut previous_blank = true;
+    let mut in_indented_code = false;
+    // Paragraph or list item which the following plain lines continue, with its number of `>` quote markers
+    let mut open: Option<(TextBlock, usize)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let trimmed = content.trim_start();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if !is_fence {
+            let indented = content.starts_with("    ") || content.starts_with('\t');
+            in_indented_code = indented && !trimmed.is_empty() && (previous_blank || in_indented_code);
+            previous_blank = trimmed.is_empty();
+        }
+        let content_start = start + markdown_prefix_len(content);
+        let content_end = start + content.len();
+        let prefix = &text[start..content_start];
+        let quotes = prefix.matches('>').count();
+        let is_table = text[content_start..content_end].starts_with('|');
+        let skipped = is_fence || trimmed.is_empty() || in_indented_code || is_link_definition(trimmed);
+
+        // Lines without list or heading markers continue the open block, like soft-wrapped paragraphs
+        let plain = prefix.chars().all(|c| c == '>' || c.is_whitespace());
+        if let Some((block, _)) = open.as_mut().filter(|(_, q)| *q == quotes && plain && !skipped && !is_table) {
+            if quotes > 0 {
+                block.add_markup(start..content_start);
+            }
+            markdown_inline(text, content_start..content_end, block);
+            continue;
+        }
+        if let Some((block, _)) = open.take() {
+            let end = block.range.end;
+            push_block(text, block, end, blocks);
+        }
+
+        if is_fence {
+            fence = Some(&trimmed[..3]);
+        } else if skipped {
+            continue;
+        } else if is_table {
+            let mut cell_start = content_start;
+            for (p, c) in text[content_start..content_end].char_indices() {
+                if c == '|' && !text[..content_start + p].ends_with('\\') {
+                    push_inline(text, cell_start..content_start + p, blocks);
+                    cell_start = content_start + p + 1;
+                }
+            }
+            push_inline(text, cell_start..content_end, blocks);
+        } else if prefix.contains('#') {
+            push_inline(text, content_start..content_end, blocks);
+        } else {
+            let mut block = TextBlock::new(content_start);
+            markdown_inline(text, content_start..content_end, &mut block);
+            open = Some((block, quotes));
+        }
+    }
+    if let Some((block, _)) = open {
+        let end = block.range.end;
+        push_block(text, block, end, blocks);
+    }
+}
+
+/// Adds the text of `range`, e.g. a heading or a table cell, as a block of its own.
+fn push_inline(text: &str, range: Range<usize>, blocks: &mut Vec<TextBlock>) {
+    let mut block = TextBlock::new(range.start);
+    markdown_inline(text, range.clone(), &mut block);
+    push_block(text, block, range.end, blocks);
+}
+
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && line.contains("]:")
+}
+
+/// Length of the block quote, heading, list and task markers at the start of a line.
+fn markdown_prefix_len(line: &str) -> usize {
+    let mut rest = line;
+    loop {
+        let trimmed = rest.trim_start();
+        let marker = if trimmed.starts_with('>') {
+            1
+        } else if trimmed.starts_with('#') {
+            let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+            if hashes <= 6 && trimmed[hashes..].starts_with(' ') { hashes } else { 0 }
+        } else if ["- ", "* ", "+ "].iter().any(|m| trimmed.starts_with(m)) {
+            1
+        } else if ["[ ] ", "[x] ", "[X] "].iter().any(|m| trimmed.starts_with(m)) {
+            3
+        } else {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") ")) {
+                digits + 1
+            } else {
+                0
+            }
+        };
+        if marker == 0 {
+            return line.len() - trimmed.len();
+        }
+        rest = &trimmed[marker..];
+    }
+}
+
+/// Adds a line of Markdown to `block`, with inline code, link targets, inline HTML, emphasis markers
+/// and escapes as its markup.
+fn markdown_inline(text: &str, range: Range<usize>, block: &mut TextBlock) {
+    let line = &text[range.clone()];
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 1 < bytes.len() {
+            block.add_markup(range.start + i..range.start + i + 1);
+            i += 1 + line[i + 1..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        let skip = match bytes[i] {
+            b'`' => {
+                let ticks = line[i..].chars().take_while(|c| *c == '`').count();
+                let fence = &line[i..i + ticks];
+                line[i + ticks..].find(fence).map(|p| i + ticks + p + ticks)
+            }
+            b'<' => html_tag_end(line, i),
+            b'*' | b'~' => Some(i + line[i..].chars().take_while(|c| *c == '*' || *c == '~').count()),
+            b'_' if !is_intraword(line, i) => Some(i + line[i..].chars().take_while(|c| *c == '_').count()),
+            b'!' if line[i + 1..].starts_with('[') => Some(i + 1),
+            b']' => match bytes.get(i + 1) {
+                Some(b'(') => Some(line[i..].find(')').map(|p| i + p + 1).unwrap_or(line.len())),
+                Some(b'[') => Some(line[i + 1..].find(']').map(|p| i + p + 2).unwrap_or(line.len())),
+                _ => Some(i + 1),
+            },
+            b'[' => Some(i + 1),
+            _ => None,
+        };
+        match skip {
+            Some(end) => {
+                block.add_markup(range.start + i..range.start + end);
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    block.range.end = range.end;
+}
+
+/// Whether the underscores at `i` are part of a word, as in `snake_case`, rather than emphasis.
+fn is_intraword(line: &str, i: usize) -> bool {
+    let before = line[..i].chars().next_back();
+    let after = line[i..].trim_start_matches('_').chars().next();
+    before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric)
+}
+
+/// Ends `block` at `end` and adds it, with the URLs in its text as further markup,
+/// if its text contains any letters.
+fn push_block(text: &str, mut block: TextBlock, end: usize, blocks: &mut Vec<TextBlock>) {
+    block.range.end = end;
+    let mut last = block.range.start;
+    for range in std::mem::take(&mut block.markup) {
+        for url in url_ranges(text, last..range.start) {
+            block.add_markup(url);
+        }
+        last = range.end;
+        block.add_markup(range);
+    }
+    for url in url_ranges(text, last..end) {
+        block.add_markup(url);
+    }
+    if block.masked(text).chars().any(char::is_alphabetic) {
+        blocks.push(block);
+    }
+}
+
+/// Byte ranges of the URLs in `range`, without trailing punctuation.
+fn url_ranges(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    let mut word_start = range.start;
+    let mut skip_to = range.start;
+    for (p, c) in text[range.clone()].char_indices() {
+        let p = range.start + p;
+        if c.is_whitespace() {
+            word_start = p + c.len_utf8();
+            continue;
+        }
+        if p == word_start && p >= skip_to && URL_PREFIXES.iter().any(|prefix| text[p..range.end].starts_with(prefix)) {
+            let end = text[p..range.end].find(char::is_whitespace).map(|e| p + e).unwrap_or(range.end);
+            let url = text[p..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+            urls.push(p..p + url.len());
+            skip_to = end;
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of a block with `{}` in place of its markup.
+    fn render(text: &str, block: &TextBlock) -> String {
+        let mut rendered = String::new();
+        let mut last = block.range.start;
+        for markup in &block.markup {
+            rendered.push_str(&text[last..markup.start]);
+            rendered.push_str("{}");
+            last = markup.end;
+        }
+        rendered.push_str(&text[last..block.range.end]);
+        rendered
+    }
+
+    #[test]
+    fn finds_translatable_blocks() {
+        let cases: [(&str, TextFormat, &str, &[&str]); 24] = [
+            ("plain text", TextFormat::Text, "<b>Hello</b> http://x.org", &["<b>Hello</b> http://x.org"]),
+            ("html inline tags", TextFormat::Html, "<p>Hello <b>world</b>!</p>", &["Hello {}world{}!"]),
+            ("html links", TextFormat::Html, "<p>Read the <a href=\"docs.html\">documentation</a> first.</p>",
+             &["Read the {}documentation{} first."]),
+            ("html attributes", TextFormat::Html, "<a href=\"/home\" title=\"Home > start\">Go home</a>",
+             &["{}Go home{}"]),
+            ("html comments", TextFormat::Html, "<!-- <p>Hidden</p> -->Shown", &["Shown"]),
+            ("html raw elements", TextFormat::Html, "<p>Run</p><pre>ls -la\nexit</pre><script>let a = 1;</script>Done",
+             &["Run", "Done"]),
+            ("html inline code", TextFormat::Html, "<p>Call <code>split()</code> first</p>", &["Call {} first"]),
+            ("html line breaks", TextFormat::Html, "<li>One<br>Two</li><li>Three</li>", &["One", "Two", "Three"]),
+            ("html urls", TextFormat::Html, "<p>See https://example.com/docs. Thanks</p>", &["See {}. Thanks"]),
+            ("html less than", TextFormat::Html, "<p>a < b is true</p>", &["a < b is true"]),
+            ("html unclosed inline code", TextFormat::Html, "？<code>1«#」", &[]),
+            ("markdown headings and lists", TextFormat::Markdown, "# Title\n\n- First item\n1. Second",
+             &["Title", "First item", "Second"]),
+            ("markdown emphasis", TextFormat::Markdown, "This is **very** important", &["This is {}very{} important"]),
+            ("markdown snake case", TextFormat::Markdown, "Set max_length now", &["Set max_length now"]),
+            ("markdown inline code", TextFormat::Markdown, "Call `split_text()` first", &["Call {} first"]),
+            ("markdown links", TextFormat::Markdown, "Read [the docs](https://x.org/a_b) and ![logo](l.png)",
+             &["Read {}the docs{} and {}logo{}"]),
+            ("markdown fenced code", TextFormat::Markdown, "Before\n```rust\nlet text = 1;\n```\nAfter",
+             &["Before", "After"]),
+            ("markdown indented code", TextFormat::Markdown, "Text\n\n    let code = 1;\nMore", &["Text", "More"]),
+            ("markdown tables", TextFormat::Markdown, "| Name | Notes |\n|---|---|\n| Anna | Likes tea |",
+             &[" Name ", " Notes ", " Anna ", " Likes tea "]),
+            ("markdown escapes", TextFormat::Markdown, "Not \\*emphasis\\* here", &["Not {}*emphasis{}* here"]),
+            ("markdown quotes and link definitions", TextFormat::Markdown, "> Quoted text\n[id]: https://x.org",
+             &["Quoted text"]),
+            ("markdown wrapped paragraph", TextFormat::Markdown,
+             "The model reads\nwhole *sentences*\n  across lines.\n\nNext one.",
+             &["The model reads\nwhole {}sentences{}\n  across lines.", "Next one."]),
+            ("markdown wrapped list items", TextFormat::Markdown, "# Title\nIntro\n- First\n  item\n- Second",
+             &["Title", "Intro", "First\n  item", "Second"]),
+            ("markdown wrapped quote", TextFormat::Markdown, "> Quoted\n> text\n\n    code\nMore",
+             &["Quoted\n{}text", "More"]),
+        ];
+        for (name, format, input, expected) in cases {
+            let blocks: Vec<String> = translatable_blocks(input, format).iter().map(|b| render(input, b)).collect();
+            assert_eq!(blocks, expected, "case '{}'", name);
+        }
+    }
+
+    #[test]
+    fn protects_and_restores_markup() {
+        let cases: [(&str, TextFormat, &str, &str); 3] = [
+            ("<p>Read the <a href=\"a.html\">docs</a> first.</p>", TextFormat::Html, "Read the docs first.",
+             "Read the ZXM0MXZdocsZXM1MXZ first."),
+            ("This is **very** important", TextFormat::Markdown, "This is very important",
+             "This is ZXM0MXZveryZXM1MXZ important"),
+            ("Plain text.", TextFormat::Text, "Plain text.", "Plain text."),
+        ];
+        for (input, format, words, protected) in cases {
+            let block = &translatable_blocks(input, format)[0];
+            let masked = block.masked(input);
+            assert_eq!(masked.len(), block.range.len(), "input '{}'", input);
+            assert_eq!(masked.split_whitespace().collect::<Vec<&str>>().join(" "), words, "input '{}'", input);
+            let start = block.range.start + masked.len() - masked.trim_start().len();
+            let segment = start..block.range.start + masked.trim_end().len();
+            let (text, markup) = block.protect(input, segment.clone());
+            assert_eq!(text, protected, "input '{}'", input);
+            assert_eq!(restore_markup(&text, &markup), input[segment].to_string(), "input '{}'", input);
+        }
+    }
+
+    #[test]
+    fn appends_markup_of_missing_placeholders() {
+        let markup = ["<a href=\"a.html\">", "</a>"];
+        let cases: [(&str, &str); 3] = [
+            ("Lesen Sie zuerst die ZXM0MXZDokuZXM1MXZ.", "Lesen Sie zuerst die <a href=\"a.html\">Doku</a>."),
+            ("Lesen Sie zuerst die ZXM0MXZDoku.", "Lesen Sie zuerst die Doku.<a href=\"a.html\"></a>"),
+            ("Lesen Sie ZXM 1 MXZ die Doku.", "Lesen Sie ZXM 1 MXZ die Doku.<a href=\"a.html\"></a>"),
+        ];
+        for (translation, restored) in cases {
+            assert_eq!(restore_markup(translation, &markup), restored, "translation '{}'", translation);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cached_path::{Cache, Options};
+use rust_bert::resources::{RemoteResource, ResourceProvider};
+use rust_bert::RustBertError;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::model_files::{ModelFiles, Pretrained, DIALOGUE_MODEL, SENTENCE_EMBEDDINGS_FILES, ZERO_SHOT_MODEL};
+use crate::summarization_factory::SummarizationConfigFactory;
+use crate::transport_structs::{FileStatus, ModelFilesStatus};
+use crate::translation_factory::{TranslationModelFactory, TranslationModelKey};
+
+const USAGE: &str = "Usage: nlp-api models list [<pipeline> [<variant>]]
+       nlp-api models fetch <pipeline> <variant>
+       nlp-api models verify [<pipeline> [<variant>]]
+       nlp-api models prune [--dry-run]";
+
+/// A model the pipelines can load, with the files it needs.
+pub(crate) struct CatalogModel {
+    pub(crate) pipeline: &'static str,
+    /// Name of the variant, as reported by the health endpoints
+    pub(crate) variant: String,
+    pub(crate) files: Vec<Pretrained>,
+}
+
+/// Every model referenced by the pipelines.
+pub(crate) fn catalog() -> Vec<CatalogModel> {
+    let model = |pipeline, variant: &str, files| CatalogModel { pipeline, variant: variant.to_string(), files };
+    let mut models: Vec<CatalogModel> = SummarizationConfigFactory::models().iter()
+        .map(|m| model("summarization", m.name, m.files.files()))
+        .collect();
+    models.extend(TranslationModelKey::all()
+        .map(|key| model("translation", &key.name(), TranslationModelFactory::files(key).files())));
+    models.push(model("zero_shot", "default", ZERO_SHOT_MODEL.files()));
+    models.push(model("sentence_embeddings", "all-MiniLM-L6-v2", SENTENCE_EMBEDDINGS_FILES.to_vec()));
+    models.push(model("dialogue", "default", DIALOGUE_MODEL.files()));
+    models
+}
+
+/// Models of the catalog matching the optional `pipeline` and `variant` filters.
+pub(crate) fn select(pipeline: Option<&str>, variant: Option<&str>) -> Vec<CatalogModel> {
+    catalog().into_iter()
+        .filter(|m| pipeline.is_none_or(|p| m.pipeline == p) && variant.is_none_or(|v| m.variant == v))
+        .collect()
+}
+
+/// Where a model file was found.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FileLocation {
+    ModelDir,
+    /// The rust-bert download cache
+    Cache,
+    Missing,
+}
+
+/// How the SHA-256 of a file compares with the one recorded when it was fetched.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Checksum {
+    Match,
+    Mismatch,
+    /// The file was not downloaded by `models fetch`, so there is nothing to compare with
+    Unknown,
+}
+
+/// Directory of the rust-bert download cache, `RUSTBERT_CACHE` or the `.rustbert` user cache directory.
+/// Fails rather than falling back to the working directory when neither is known.
+fn cache_dir() -> io::Result<PathBuf> {
+    match std::env::var("RUSTBERT_CACHE") {
+        Ok(dir) => Ok(PathBuf::from(dir)),
+        Err(_) => dirs::cache_dir()
+            .map(|dir| dir.join(".rustbert"))
+            .ok_or(io::Error::new(io::ErrorKind::NotFound,
+                                  "No user cache directory, set RUSTBERT_CACHE to the rust-bert cache")),
+    }
+}
+
+/// Finds a file in `model_dir` or in the download cache, without downloading anything.
+pub(crate) fn locate(files: &ModelFiles, file: Pretrained) -> (FileLocation, Option<PathBuf>) {
+    if let Some(path) = files.local_path(file).filter(|path| path.is_file()) {
+        return (FileLocation::ModelDir, Some(path));
+    }
+    let Ok(dir) = cache_dir() else {
+        return (FileLocation::Missing, None);
+    };
+    let cached = Cache::builder()
+        .dir(dir)
+        .offline(true)
+        .progress_bar(None)
+        .build()
+        .and_then(|cache| cache.cached_path_with_options(file.1, &Options::default().subdir(file.0)));
+    match cached {
+        Ok(path) => (FileLocation::Cache, Some(path)),
+        Err(_) => (FileLocation::Missing, None),
+    }
+}
+
+/// Location and size of the files of `model`. If `checksums` is set, also their SHA-256 compared with
+/// the one recorded at fetch time.
+pub(crate) fn model_status(files: &ModelFiles, model: &CatalogModel, checksums: bool) -> io::Result<ModelFilesStatus> {
+    let mut statuses = Vec::new();
+    for file in &model.files {
+        let (location, path) = locate(files, *file);
+        let size = path.as_ref().map(|p| fs::metadata(p).map(|m| m.len())).transpose()?;
+        let (sha256, expected_sha256, checksum) = match &path {
+            Some(path) if checksums => {
+                let actual = sha256(path)?;
+                let expected = expected_sha256(path)?;
+                let checksum = match &expected {
+                    Some(expected) if *expected == actual => Checksum::Match,
+                    Some(_) => Checksum::Mismatch,
+                    None => Checksum::Unknown,
+                };
+                (Some(actual), expected, Some(checksum))
+            }
+            _ => (None, None, None),
+        };
+        statuses.push(FileStatus {
+            name: file.0.to_string(),
+            url: file.1.to_string(),
+            location,
+            path: path.map(|p| p.display().to_string()),
+            size,
+            sha256,
+            expected_sha256,
+            checksum,
+        });
+    }
+    Ok(ModelFilesStatus {
+        pipeline: model.pipeline.to_string(),
+        variant: model.variant.clone(),
+        complete: statuses.iter().all(|f| f.location != FileLocation::Missing),
+        size: statuses.iter().filter_map(|f| f.size).sum(),
+        files: statuses,
+    })
+}
+
+fn sha256(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// File next to a model file holding the SHA-256 recorded when it was fetched, e.g. `rust_model.ot.sha256`.
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sha256");
+    path.with_file_name(name)
+}
+
+fn expected_sha256(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(checksum_path(path)) {
+        Ok(sha256) => Ok(Some(sha256.trim().to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Downloads the missing files of `model`, into `model_dir` if it is set and into the cache otherwise,
+/// and records the SHA-256 of each downloaded file for `models verify`.
+pub(crate) fn fetch(files: &ModelFiles, model: &CatalogModel) -> Result<Vec<PathBuf>, RustBertError> {
+    let mut paths = Vec::new();
+    for file in &model.files {
+        if let (FileLocation::ModelDir, Some(path)) = locate(files, *file) {
+            paths.push(path);
+            continue;
+        }
+        let cached = RemoteResource::from_pretrained(*file).get_local_path()?;
+        let path = match files.local_path(*file) {
+            Some(local_path) => {
+                let copy = |from: &Path, to: &Path| -> io::Result<()> {
+                    fs::create_dir_all(to.parent().unwrap_or(Path::new(".")))?;
+                    fs::copy(from, to).map(|_| ())
+                };
+                copy(&cached, &local_path).map_err(|e| RustBertError::IOError(
+                    format!("Cannot copy {} to {}: {}", cached.display(), local_path.display(), e)))?;
+                local_path
+            }
+            None => cached,
+        };
+        let record = |path: &Path| fs::write(checksum_path(path), sha256(path)?);
+        record(&path).map_err(|e| RustBertError::IOError(
+            format!("Cannot record the SHA-256 of {}: {}", path.display(), e)))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Removes the directories of the download cache which no model of the catalog refers to, or only lists
+/// them if `dry_run` is set. Returns the removed directories with their size.
+pub(crate) fn prune(dry_run: bool) -> io::Result<Vec<(PathBuf, u64)>> {
+    let root = cache_dir()?;
+    let referenced: HashSet<PathBuf> = catalog().iter()
+        .flat_map(|m| m.files.iter().map(|(name, _)| PathBuf::from(name)))
+        .collect();
+    let mut removed = Vec::new();
+    if root.is_dir() {
+        prune_dir(&root, Path::new(""), &referenced, dry_run, &mut removed)?;
+    }
+    Ok(removed)
+}
+
+fn prune_dir(root: &Path, relative: &Path, referenced: &HashSet<PathBuf>, dry_run: bool,
+             removed: &mut Vec<(PathBuf, u64)>) -> io::Result<()> {
+    for entry in fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = relative.join(entry.file_name());
+        if referenced.contains(&path) {
+            continue;
+        }
+        if referenced.iter().any(|r| r.starts_with(&path)) {
+            prune_dir(root, &path, referenced, dry_run, removed)?;
+        } else {
+            let size = dir_size(&entry.path())?;
+            if !dry_run {
+                fs::remove_dir_all(entry.path())?;
+            }
+            removed.push((entry.path(), size));
+        }
+    }
+    Ok(())
+}
+
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        size += if entry.file_type()?.is_dir() { dir_size(&entry.path())? } else { entry.metadata()?.len() };
+    }
+    Ok(size)
+}
+
+/// Runs the `models` subcommand of the binary.
+pub(crate) fn run_command(args: &[String], files: &ModelFiles) -> io::Result<()> {
+    let arg = |i: usize| args.get(i).map(|a| a.as_str());
+    match (arg(0), arg(1), arg(2)) {
+        (Some("list"), pipeline, variant) => {
+            for model in select(pipeline, variant) {
+                let status = model_status(files, &model, false)?;
+                let found = status.files.iter().filter(|f| f.location != FileLocation::Missing).count();
+                println!("{:<20} {:<40} {:>3}/{} files {:>12} bytes", status.pipeline, status.variant, found,
+                         status.files.len(), status.size);
+            }
+            Ok(())
+        }
+        (Some("fetch"), Some(pipeline), Some(variant)) => {
+            let model = select(Some(pipeline), Some(variant)).into_iter().next()
+                .ok_or(io::Error::new(io::ErrorKind::NotFound,
+                                      format!("Unknown model {} {}, see `models list`", pipeline, variant)))?;
+            let paths = fetch(files, &model).map_err(|e| io::Error::other(e.to_string()))?;
+            for path in paths {
+                println!("{}", path.display());
+            }
+            Ok(())
+        }
+        (Some("verify"), pipeline, variant) => {
+            let mut incomplete = 0;
+            let mut mismatches = 0;
+            for model in select(pipeline, variant) {
+                let status = model_status(files, &model, true)?;
+                println!("{} {}", status.pipeline, status.variant);
+                for file in &status.files {
+                    let checksum = file.checksum.map(|c| format!("{:?}", c).to_lowercase());
+                    println!("  {:<9} {:>12} {:<64} {:<8} {}", format!("{:?}", file.location).to_lowercase(),
+                             file.size.unwrap_or(0), file.sha256.as_deref().unwrap_or("-"),
+                             checksum.as_deref().unwrap_or("-"), file.path.as_deref().unwrap_or(&file.url));
+                    if file.checksum == Some(Checksum::Mismatch) {
+                        println!("  expected {}", file.expected_sha256.as_deref().unwrap_or("-"));
+                        mismatches += 1;
+                    }
+                }
+                if !status.complete {
+                    incomplete += 1;
+                }
+            }
+            if mismatches > 0 {
+                Err(io::Error::new(io::ErrorKind::InvalidData,
+                                   format!("{} files do not match the SHA-256 recorded when fetched", mismatches)))
+            } else if incomplete > 0 {
+                Err(io::Error::new(io::ErrorKind::NotFound, format!("{} models have missing files", incomplete)))
+            } else {
+                Ok(())
+            }
+        }
+        (Some("prune"), flag, None) if flag.is_none_or(|f| f == "--dry-run") => {
+            let dry_run = flag.is_some();
+            let removed = prune(dry_run)?;
+            let (action, total) = if dry_run { ("Would remove", "Would free") } else { ("Removed", "Freed") };
+            for (path, size) in &removed {
+                println!("{} {} ({} bytes)", action, path.display(), size);
+            }
+            println!("{} {} bytes", total, removed.iter().map(|(_, size)| size).sum::<u64>());
+            Ok(())
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, file: &str, size: usize) {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; size]).unwrap();
+    }
+
+    #[test]
+    fn prunes_unreferenced_directories() {
+        let root = std::env::temp_dir().join(format!("nlp-api-prune-{}", std::process::id()));
+        write(&root, "bart-cnn/model/weights", 10);
+        write(&root, "other-project/model/weights", 20);
+        write(&root, "marian/en-de/vocab", 1);
+        write(&root, "marian/unused/vocab", 5);
+        write(&root, "loose-file", 3);
+        let referenced: HashSet<PathBuf> = ["bart-cnn/model", "marian/en-de"].iter().map(PathBuf::from).collect();
+
+        for dry_run in [true, false] {
+            let mut removed = Vec::new();
+            prune_dir(&root, Path::new(""), &referenced, dry_run, &mut removed).unwrap();
+            removed.sort();
+            assert_eq!(removed, vec![(root.join("marian/unused"), 5), (root.join("other-project"), 20)],
+                       "dry run {}", dry_run);
+            assert_eq!(root.join("other-project").is_dir(), dry_run, "dry run {}", dry_run);
+            assert_eq!(root.join("marian/unused").is_dir(), dry_run, "dry run {}", dry_run);
+            assert!(root.join("bart-cnn/model/weights").is_file() && root.join("marian/en-de/vocab").is_file());
+            assert!(root.join("loose-file").is_file());
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+
+use rust_bert::bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources};
+use rust_bert::bert::{BertConfigResources, BertModelResources, BertVocabResources};
+use rust_bert::gpt2::{Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources};
+use rust_bert::pipelines::common::ModelResource;
+use rust_bert::pipelines::conversation::ConversationConfig;
+use rust_bert::pipelines::sentence_embeddings::{SentenceEmbeddingsConfig, SentenceEmbeddingsConfigResources,
+                                                SentenceEmbeddingsModelType, SentenceEmbeddingsModulesConfigResources,
+                                                SentenceEmbeddingsPoolingConfigResources,
+                                                SentenceEmbeddingsTokenizerConfigResources};
+use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationConfig;
+use rust_bert::resources::{LocalResource, RemoteResource, ResourceProvider};
+use rust_bert::RustBertError;
+
+/// A file of a pretrained model as declared by rust-bert, as (cache name, download URL).
+pub(crate) type Pretrained = (&'static str, &'static str);
+
+/// A model file ready to be passed to a pipeline configuration.
+pub(crate) type Resource = Box<dyn ResourceProvider + Send>;
+
+/// Files of a pretrained model. Sentencepiece models use `merges` for their `.spm` or `.model` file.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PretrainedModel {
+    pub(crate) model: Pretrained,
+    pub(crate) config: Pretrained,
+    pub(crate) vocab: Pretrained,
+    pub(crate) merges: Option<Pretrained>,
+}
+
+impl PretrainedModel {
+    pub(crate) fn files(&self) -> Vec<Pretrained> {
+        [Some(self.model), Some(self.config), Some(self.vocab), self.merges].into_iter().flatten().collect()
+    }
+}
+
+/// Resources of a pretrained model, read from `model_dir` or downloaded.
+pub(crate) struct ModelResources {
+    pub(crate) model: ModelResource,
+    pub(crate) config: Resource,
+    pub(crate) vocab: Resource,
+    pub(crate) merges: Option<Resource>,
+}
+
+pub(crate) const ZERO_SHOT_MODEL: PretrainedModel = PretrainedModel {
+    model: BartModelResources::BART_MNLI,
+    config: BartConfigResources::BART_MNLI,
+    vocab: BartVocabResources::BART_MNLI,
+    merges: Some(BartMergesResources::BART_MNLI),
+};
+
+pub(crate) const DIALOGUE_MODEL: PretrainedModel = PretrainedModel {
+    model: Gpt2ModelResources::DIALOGPT_MEDIUM,
+    config: Gpt2ConfigResources::DIALOGPT_MEDIUM,
+    vocab: Gpt2VocabResources::DIALOGPT_MEDIUM,
+    merges: Some(Gpt2MergesResources::DIALOGPT_MEDIUM),
+};
+
+/// Files of the all-MiniLM-L6-v2 sentence embeddings model, used by keyword extraction and
+/// extractive summarization: modules, transformer config and weights, pooling config,
+/// sentence-bert config, tokenizer config and vocabulary.
+pub(crate) const SENTENCE_EMBEDDINGS_FILES: [Pretrained; 7] = [
+    SentenceEmbeddingsModulesConfigResources::ALL_MINI_LM_L6_V2,
+    BertConfigResources::ALL_MINI_LM_L6_V2,
+    BertModelResources::ALL_MINI_LM_L6_V2,
+    SentenceEmbeddingsPoolingConfigResources::ALL_MINI_LM_L6_V2,
+    SentenceEmbeddingsConfigResources::ALL_MINI_LM_L6_V2,
+    SentenceEmbeddingsTokenizerConfigResources::ALL_MINI_LM_L6_V2,
+    BertVocabResources::ALL_MINI_LM_L6_V2,
+];
+
+/// Where model files are read from, configured through the `model_dir` and `allow_remote_models`
+/// settings. Files found in `model_dir` are used as they are; missing files are downloaded into
+/// the rust-bert cache only if remote models are allowed.
+#[derive(Debug, Clone)]
+pub(crate) struct ModelFiles {
+    dir: Option<PathBuf>,
+    allow_remote: bool,
+}
+
+impl ModelFiles {
+    pub(crate) fn new(dir: &str, allow_remote: bool) -> Result<ModelFiles, String> {
+        if dir.is_empty() {
+            return if allow_remote {
+                Ok(ModelFiles { dir: None, allow_remote })
+            } else {
+                Err(String::from("model_dir must be set when allow_remote_models is false"))
+            };
+        }
+        if !Path::new(dir).is_dir() {
+            return Err(format!("model_dir '{}' is not a directory", dir));
+        }
+        Ok(ModelFiles { dir: Some(PathBuf::from(dir)), allow_remote })
+    }
+
+    /// Path of a file in `model_dir`, following the layout of the Hugging Face repository of the
+    /// model in a directory named after the rust-bert model: `bart-cnn/vocab` downloaded from
+    /// `.../resolve/main/vocab.json` is `<model_dir>/bart-cnn/vocab.json`.
+    pub(crate) fn local_path(&self, file: Pretrained) -> Option<PathBuf> {
+        let (name, url) = file;
+        let model = name.split('/').next().unwrap_or(name);
+        let path = url.split_once("/resolve/main/")
+            .map(|(_, path)| path)
+            .unwrap_or(url.rsplit('/').next().unwrap_or(url));
+        self.dir.as_ref().map(|dir| dir.join(model).join(path))
+    }
+
+    /// Fails with the list of files of `model` which are not in `model_dir`, unless they may be downloaded.
+    pub(crate) fn check(&self, model: &str, files: &[Pretrained]) -> Result<(), RustBertError> {
+        if self.allow_remote {
+            return Ok(());
+        }
+        let missing: Vec<String> = files.iter()
+            .filter_map(|file| self.local_path(*file))
+            .filter(|path| !path.is_file())
+            .map(|path| path.display().to_string())
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(RustBertError::IOError(format!(
+                "Missing files of model {} and allow_remote_models is false: {}", model, missing.join(", "))))
+        }
+    }
+
+    /// The file in `model_dir` if present, the remote file otherwise. Call `check` first.
+    pub(crate) fn resource(&self, file: Pretrained) -> Resource {
+        match self.local_path(file).filter(|path| path.is_file()) {
+            Some(local_path) => Box::new(LocalResource { local_path }),
+            None => Box::new(RemoteResource::from_pretrained(file)),
+        }
+    }
+
+    pub(crate) fn model(&self, name: &str, model: &PretrainedModel) -> Result<ModelResources, RustBertError> {
+        self.check(name, &model.files())?;
+        Ok(ModelResources {
+            model: ModelResource::Torch(self.resource(model.model)),
+            config: self.resource(model.config),
+            vocab: self.resource(model.vocab),
+            merges: model.merges.map(|merges| self.resource(merges)),
+        })
+    }
+
+    pub(crate) fn zero_shot_config(&self) -> Result<ZeroShotClassificationConfig, RustBertError> {
+        let resources = self.model("zero_shot", &ZERO_SHOT_MODEL)?;
+        Ok(ZeroShotClassificationConfig {
+            model_resource: resources.model,
+            config_resource: resources.config,
+            vocab_resource: resources.vocab,
+            merges_resource: resources.merges,
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn conversation_config(&self) -> Result<ConversationConfig, RustBertError> {
+        let resources = self.model("dialogue", &DIALOGUE_MODEL)?;
+        Ok(ConversationConfig {
+            model_resource: resources.model,
+            config_resource: resources.config,
+            vocab_resource: resources.vocab,
+            merges_resource: resources.merges,
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn sentence_embeddings_config(&self) -> Result<SentenceEmbeddingsConfig, RustBertError> {
+        self.check("sentence_embeddings", &SENTENCE_EMBEDDINGS_FILES)?;
+        let [modules, transformer_config, transformer_weights, pooling, sentence_bert, tokenizer_config, vocab] =
+            SENTENCE_EMBEDDINGS_FILES.map(|file| self.resource(file));
+        Ok(SentenceEmbeddingsConfig {
+            modules_config_resource: modules,
+            transformer_config_resource: transformer_config,
+            transformer_weights_resource: transformer_weights,
+            pooling_config_resource: pooling,
+            sentence_bert_config_resource: sentence_bert,
+            tokenizer_config_resource: tokenizer_config,
+            tokenizer_vocab_resource: vocab,
+            ..SentenceEmbeddingsConfig::from(SentenceEmbeddingsModelType::AllMiniLmL6V2)
+        })
+    }
+}
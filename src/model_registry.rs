@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rust_bert::pipelines::conversation::ConversationModel;
+use rust_bert::pipelines::keywords_extraction::KeywordExtractionModel;
+use rust_bert::pipelines::translation::{Language, TranslationModel};
+use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+use rust_bert::RustBertError;
+use serde::Serialize;
+
+use crate::model_files::ModelFiles;
+use crate::nlp::{KeywordConfigFactory, DEFAULT_HOW_MANY, DEFAULT_NGRAM_RANGE};
+use crate::summarization_factory::{SummarizationConfigFactory, Summarizer};
+use crate::transport_structs::ModelStatus;
+use crate::translation_factory::{TranslationBackend, TranslationModelFactory, TranslationModelKey};
+
+/// A loaded model shared between requests. The mutex serializes inference on the model,
+/// since the underlying tensors are not `Sync`.
+pub(crate) type SharedModel<M> = Arc<Mutex<M>>;
+
+/// Locks a shared model for inference. A panic during inference poisons the mutex, but does not
+/// leave the model unusable, so the poison is ignored rather than failing every later request.
+pub(crate) fn lock_model<M>(model: &SharedModel<M>) -> MutexGuard<'_, M> {
+    model.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Slot holding a model once it has been loaded.
+type ModelSlot<M> = Arc<Mutex<Option<SharedModel<M>>>>;
+
+/// Load state of a single model variant, as reported by the health endpoints.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", content = "error", rename_all = "snake_case")]
+pub(crate) enum ModelState {
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+/// Long-lived model instances of a single pipeline, keyed by variant and created on first use.
+pub(crate) struct ModelSlots<K, M> {
+    slots: Mutex<HashMap<K, ModelSlot<M>>>,
+    states: Mutex<HashMap<String, ModelState>>,
+    variant_name: fn(&K) -> String,
+}
+
+impl<K: Eq + Hash + Clone, M> ModelSlots<K, M> {
+    fn new(variant_name: fn(&K) -> String) -> Self {
+        ModelSlots {
+            slots: Mutex::new(HashMap::new()),
+            states: Mutex::new(HashMap::new()),
+            variant_name,
+        }
+    }
+
+    fn set_state(&self, key: &K, state: ModelState) {
+        self.states.lock().unwrap_or_else(PoisonError::into_inner).insert((self.variant_name)(key), state);
+    }
+
+    /// Returns the state of every variant which has been requested so far, sorted by variant.
+    pub(crate) fn states(&self) -> Vec<(String, ModelState)> {
+        let mut states: Vec<(String, ModelState)> = self.states.lock().unwrap_or_else(PoisonError::into_inner).iter()
+            .map(|(variant, state)| (variant.clone(), state.clone()))
+            .collect();
+        states.sort_by(|a, b| a.0.cmp(&b.0));
+        states
+    }
+
+    /// Returns the model for `key`, running `loader` if it has not been loaded yet.
+    /// Concurrent callers asking for the same key wait for a single load,
+    /// while different keys load in parallel.
+    pub(crate) fn get_or_load<F>(&self, key: &K, loader: F) -> Result<SharedModel<M>, RustBertError>
+        where F: FnOnce() -> Result<M, RustBertError> {
+        let slot = {
+            let mut slots = self.slots.lock().unwrap_or_else(PoisonError::into_inner);
+            slots.entry(key.clone()).or_default().clone()
+        };
+        // A loader which panicked left the slot empty, so the next caller loads the model again
+        let mut slot_guard = slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(model) = slot_guard.as_ref() {
+            return Ok(model.clone());
+        }
+        self.set_state(key, ModelState::Loading);
+        match loader() {
+            Ok(model) => {
+                let model = Arc::new(Mutex::new(model));
+                *slot_guard = Some(model.clone());
+                self.set_state(key, ModelState::Loaded);
+                Ok(model)
+            }
+            Err(e) => {
+                self.set_state(key, ModelState::Failed(e.to_string()));
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Registry owning every rust-bert pipeline used by the API so that weights are loaded once
+/// and reused across requests.
+pub(crate) struct ModelRegistry {
+    summarization: ModelSlots<String, Summarizer>,
+    translation: ModelSlots<TranslationModelKey, TranslationModel>,
+    zero_shot: ModelSlots<(), ZeroShotClassificationModel>,
+    keywords: ModelSlots<(), KeywordExtractionModel<'static>>,
+    dialogue: ModelSlots<(), ConversationModel>,
+    files: ModelFiles,
+    /// Set once all models configured for preloading are loaded.
+    ready: AtomicBool,
+}
+
+impl ModelRegistry {
+    pub(crate) fn new(files: ModelFiles) -> Self {
+        ModelRegistry {
+            summarization: ModelSlots::new(|variant| variant.clone()),
+            translation: ModelSlots::new(|key| key.name()),
+            zero_shot: ModelSlots::new(|_| String::from("default")),
+            keywords: ModelSlots::new(|_| String::from("all-MiniLM-L6-v2")),
+            dialogue: ModelSlots::new(|_| String::from("default")),
+            files,
+            ready: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn mark_ready(&self) {
+        self.ready.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    /// Load state of every requested model variant, grouped by pipeline.
+    /// Pipelines without any requested variant are listed with no models.
+    pub(crate) fn model_statuses(&self) -> BTreeMap<&'static str, Vec<ModelStatus>> {
+        let to_statuses = |states: Vec<(String, ModelState)>| states.into_iter()
+            .map(|(variant, state)| ModelStatus { variant, state })
+            .collect::<Vec<ModelStatus>>();
+        BTreeMap::from([
+            ("summarization", to_statuses(self.summarization.states())),
+            ("translation", to_statuses(self.translation.states())),
+            ("zero_shot", to_statuses(self.zero_shot.states())),
+            ("keywords", to_statuses(self.keywords.states())),
+            ("dialogue", to_statuses(self.dialogue.states())),
+        ])
+    }
+
+    /// Returns the summarization model `variant`. Generation settings are passed per call,
+    /// so every variant is loaded once.
+    pub(crate) fn summarization(&self, variant: &str) -> Result<SharedModel<Summarizer>, RustBertError> {
+        self.summarization.get_or_load(&variant.to_string(), || {
+            Summarizer::new(SummarizationConfigFactory::find(variant)
+                .unwrap_or(SummarizationConfigFactory::default_model())
+                .config(&self.files)?)
+        })
+    }
+
+    /// Returns the model of `backend` which translates from `source` to `target`.
+    /// Many-to-many backends share one model between all language pairs.
+    pub(crate) fn translation(&self, backend: TranslationBackend, source: Language, target: Language)
+        -> Result<SharedModel<TranslationModel>, RustBertError> {
+        let key = TranslationModelKey::for_pair(backend, source, target)
+            .ok_or(RustBertError::InvalidConfigurationError(format!(
+                "No {} model translates from {} to {}", backend.name(), source, target)))?;
+        self.translation.get_or_load(&key, || TranslationModelFactory::create(key, &self.files))
+    }
+
+    pub(crate) fn zero_shot(&self) -> Result<SharedModel<ZeroShotClassificationModel>, RustBertError> {
+        self.zero_shot.get_or_load(&(), || ZeroShotClassificationModel::new(self.files.zero_shot_config()?))
+    }
+
+    /// Returns the keyword extraction model. The number of keywords and the n-gram sizes are
+    /// chosen per call, so there is one model per embeddings model. Its sentence embeddings model
+    /// also serves extractive summaries, so that all-MiniLM-L6-v2 is loaded once.
+    pub(crate) fn keywords(&self) -> Result<SharedModel<KeywordExtractionModel<'static>>, RustBertError> {
+        self.keywords.get_or_load(&(), || {
+            KeywordExtractionModel::new(KeywordConfigFactory::variable_keyword_number_ngram(
+                self.files.sentence_embeddings_config()?, DEFAULT_HOW_MANY, DEFAULT_NGRAM_RANGE))
+        })
+    }
+
+    pub(crate) fn dialogue(&self) -> Result<SharedModel<ConversationModel>, RustBertError> {
+        self.dialogue.get_or_load(&(), || ConversationModel::new(self.files.conversation_config()?))
+    }
+}
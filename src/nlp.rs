@@ -1,145 +1,254 @@
-use std::str::FromStr;
-use std::sync::mpsc::channel;
-use std::thread;
+use std::ops::Range;
 
 use actix_web::web;
-use rust_bert::pipelines::conversation::{ConversationManager, ConversationModel};
+use rust_bert::pipelines::conversation::ConversationManager;
 use rust_bert::pipelines::keywords_extraction::{Keyword, KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType};
-use rust_bert::pipelines::sentence_embeddings::{SentenceEmbeddingsConfig, SentenceEmbeddingsModelType};
+use rust_bert::pipelines::sentence_embeddings::SentenceEmbeddingsConfig;
 use rust_bert::pipelines::sequence_classification::Label;
-use rust_bert::pipelines::summarization::{SummarizationModel};
-use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
-use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+use rust_bert::pipelines::translation::{Language, TranslationModel};
 use rust_bert::RustBertError;
-use threadpool::ThreadPool;
 
+use crate::chunking::{chunk_text, SummarizationStrategy};
+use crate::errors::ApiError;
+use crate::extractive::{centrality_scores, cosine_similarity, top_indices, MAX_INPUT_SENTENCES};
+use crate::glossary::Glossary;
+use crate::inference::InferencePool;
+use crate::jobs::Progress;
 use crate::KeywordExtractionRequest;
-use crate::summarization_factory::SummarizationConfigFactory;
-
-#[derive(Debug)]
-pub(crate) enum SupportedLanguage {
-    Fr,
-    Hi,
-    Pt,
-    En,
-    De,
-    Nl,
+use crate::language_detection::{detect_language, DetectedLanguage};
+use crate::markup::{restore_markup, translatable_blocks, TextFormat};
+use crate::languages::SupportedLanguage;
+use crate::model_registry::{lock_model, ModelRegistry};
+use crate::segmentation::{join_lines, split_segments, split_sentences};
+use crate::summarization_factory::{GenerationOptions, SummarizationConfigFactory, SummarizationModelInfo, Summarizer};
+use crate::translation_factory::{TranslationBackend, TranslationModelKey};
+
+pub(crate) const DEFAULT_HOW_MANY: usize = 5;
+pub(crate) const DEFAULT_NGRAM_RANGE: (usize, usize) = (1, 1);
+const MAX_HOW_MANY: usize = 100;
+/// Largest number of words of a keyword
+const MAX_NGRAM_SIZE: usize = 5;
+/// Tokens of the summarization input reserved for special tokens added by the tokenizer
+const SPECIAL_TOKENS_MARGIN: usize = 8;
+const CHUNK_OVERLAP_TOKENS: usize = 64;
+/// Chunks summarized per model call, larger batches of long chunks exhaust the memory of the model
+const SUMMARY_BATCH_SIZE: usize = 4;
+/// Maximum number of times summaries of summaries are chunked again before the final summary
+const MAX_REDUCE_ROUNDS: usize = 3;
+/// Sentences encoded per call of the sentence embeddings model
+const EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// Byte ranges of the sentences of an input with the outputs of a pipeline for each of them.
+pub(crate) type SentenceOutputs<T> = (Vec<Range<usize>>, Vec<Vec<T>>);
+
+/// Languages, model and glossary of a translation request.
+#[derive(Clone)]
+pub(crate) struct TranslationPlan {
+    pub(crate) target: SupportedLanguage,
+    pub(crate) source: SupportedLanguage,
+    /// Set if the source language was detected rather than given
+    pub(crate) detected: Option<DetectedLanguage>,
+    pub(crate) backend: TranslationBackend,
+    pub(crate) glossary: Glossary,
 }
 
-impl FromStr for SupportedLanguage {
-    type Err = ();
-
-    fn from_str(input: &str) -> Result<SupportedLanguage, Self::Err> {
-        match input {
-            "fr" => Ok(SupportedLanguage::Fr),
-            "pt" => Ok(SupportedLanguage::Pt),
-            "hi" => Ok(SupportedLanguage::Hi),
-            "de" => Ok(SupportedLanguage::De),
-            "nl" => Ok(SupportedLanguage::Nl),
-            "en" => Ok(SupportedLanguage::En),
-            _ => Err(()),
-        }
-    }
+pub(crate) async fn translate_input(plan: TranslationPlan, input: String, format: TextFormat,
+                                    pool: &InferencePool,
+                                    registry: web::Data<ModelRegistry>) -> Result<Translation, ApiError> {
+    pool.run(move || translate_text(&plan, &input, format, &registry)).await
 }
 
-pub(crate) async fn translate_input(target_language: SupportedLanguage,
-                                    source_language: SupportedLanguage,
-                                    input: String) -> Result<String, RustBertError> {
-    println!("Converting from {:?} to {:?}", source_language, target_language);
-
-    return thread::spawn(move || {
-        let source_lang = convert_language(source_language);
-        let selected_lang = convert_language(target_language);
-
-        let model = TranslationModelBuilder::new()
-            .with_source_languages(vec![source_lang])
-            .with_target_languages(vec![selected_lang])
-            .create_model()?;
-        let splits = split_text(input.clone());
-        let output = model.translate(&splits, None,
-                                     selected_lang)?;
-
-        Ok(output.join(""))
-    }).join().expect("Failed");
+/// Translates a single text, blocking until the model is done.
+pub(crate) fn translate_text(plan: &TranslationPlan, input: &str, format: TextFormat, registry: &ModelRegistry)
+                             -> Result<Translation, ApiError> {
+    println!("Converting from {:?} to {:?} with {:?}", plan.source, plan.target, plan.backend);
+    let source_lang = plan.source.language();
+    let selected_lang = plan.target.language();
+
+    let model = registry.translation(plan.backend, source_lang, selected_lang).map_err(ApiError::model_load)?;
+    let mut output = translate_documents(&lock_model(&model), &[input.to_string()], format, &plan.glossary,
+                                         source_lang, selected_lang)?;
+    Ok(output.remove(0))
 }
 
-fn split_text(input: String) -> Vec<String> {
-    let mut vec: Vec<String> = Vec::new();
-    let string_bytes = input.as_bytes();
-    let mut start = 0;
-    for (i, c) in input.char_indices() {
-        if (c == '.' && (i < input.len() - 1 && (string_bytes[i + 1] as char) != '.' && !(string_bytes[i + 1] as char).is_numeric())) ||
-            (i == input.len() - 1) {
-            let slice = &input[start..i + 1].trim().to_string();
-            println!("{}", slice);
-            vec.push(slice.clone());
-            start = i + 1;
-        }
-    }
-    if vec.len() == 0 {
-        vec.push(input);
-    }
-    return vec;
+/// Translations of a batch of texts into one target language.
+pub(crate) struct TargetTranslations {
+    pub(crate) target: SupportedLanguage,
+    pub(crate) model: String,
+    pub(crate) translations: Vec<String>,
 }
 
-pub async fn zero_shot_classification(input: String, split: bool, labels: &Vec<String>)
-                                      -> Result<(Vec<String>, Vec<Vec<Label>>), RustBertError> {
-    let label_copy: Vec<String> = labels.iter().map(|l| l.clone()).collect();
-    return thread::spawn(move || {
-        let vec = handle_split(input, split);
-        let splits: Vec<&str> = vec.iter().map(|s| s.as_str()).collect();
-
-        let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
-
-        let candidate_labels: Vec<&str> = label_copy.iter().map(|s| s.as_str()).collect();
-        let output = sequence_classification_model.predict_multilabel(
-            &splits,
-            candidate_labels,
-            None,
-            128,
-        );
-        return match output {
-            Ok(vecs) => {
-                let orig = splits.iter().map(|s| s.to_string()).collect();
-                let success = Ok((orig, vecs));
-                success
+/// Translates every text into every target language. Targets served by the same model, such as
+/// the many-to-many backends or a multi-target Marian model, share one model lock.
+pub(crate) async fn translate_batch(targets: Vec<(SupportedLanguage, TranslationBackend)>,
+                                    source_language: SupportedLanguage,
+                                    texts: Vec<String>,
+                                    format: TextFormat,
+                                    glossary: Glossary,
+                                    pool: &InferencePool,
+                                    registry: web::Data<ModelRegistry>) -> Result<Vec<TargetTranslations>, ApiError> {
+    println!("Converting {} texts from {:?} to {:?}", texts.len(), source_language, targets);
+
+    pool.run(move || {
+        let source_lang = source_language.language();
+
+        let mut groups: Vec<(TranslationModelKey, Vec<(SupportedLanguage, TranslationBackend)>)> = Vec::new();
+        for (target, backend) in targets {
+            let key = TranslationModelKey::for_pair(backend, source_lang, target.language())
+                .ok_or_else(|| ApiError::ModelLoad(format!(
+                    "No {} model translates from {:?} to {:?}", backend.name(), source_language, target)))?;
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, group)) => group.push((target, backend)),
+                None => groups.push((key, vec![(target, backend)]))
             }
-            Err(e) => {
-                let error = Err(e);
-                error
+        }
+
+        let mut results = Vec::new();
+        for (key, group) in groups {
+            let (first_target, backend) = group[0];
+            let model = registry.translation(backend, source_lang, first_target.language())
+                .map_err(ApiError::model_load)?;
+            let model = lock_model(&model);
+            for (target, _) in group {
+                results.push(TargetTranslations {
+                    target,
+                    model: key.name(),
+                    translations: translate_documents(&model, &texts, format, &glossary, source_lang, target.language())?
+                        .into_iter()
+                        .map(|t| t.text)
+                        .collect(),
+                });
             }
-        };
-    }).join().expect("Failed zero shot classification");
+        }
+        Ok(results)
+    }).await
 }
 
-fn handle_split(input: String, split: bool) -> Vec<String> {
-    let vec = if split { split_text(input.clone()) } else { vec!(input.clone()) };
-    vec
+/// A translated sentence with its byte range in the original text.
+pub(crate) struct TranslatedSegment {
+    pub(crate) source: Range<usize>,
+    pub(crate) translation: String,
 }
 
-struct KeywordConfigFactory;
+/// A translated document and the translations of its sentences.
+pub(crate) struct Translation {
+    pub(crate) text: String,
+    pub(crate) segments: Vec<TranslatedSegment>,
+}
 
-impl KeywordConfigFactory {
-    fn variable_keyword_number<'a>(how_many: usize) -> KeywordExtractionConfig<'a> {
-        let sentence_embeddings_config =
-            SentenceEmbeddingsConfig::from(SentenceEmbeddingsModelType::AllMiniLmL6V2);
+/// A sentence to translate, with the inline markup inside it replaced by placeholders.
+struct SourceSegment<'a> {
+    range: Range<usize>,
+    text: String,
+    markup: Vec<&'a str>,
+}
 
-        KeywordExtractionConfig {
-            sentence_embeddings_config,
-            tokenizer_stopwords: None,
-            tokenizer_pattern: None,
-            scorer_type: KeywordScorerType::CosineSimilarity,
-            ngram_range: (1, 1),
-            num_keywords: how_many,
-            diversity: None,
-            max_sum_candidates: None,
-        }
+/// Translates the text blocks of documents in the given format sentence by sentence. The sentences
+/// of all documents are translated in a single model call and each document is reassembled from
+/// its translated sentences, keeping the markup, whitespace and line breaks between them.
+fn translate_documents(model: &TranslationModel, documents: &[String], format: TextFormat, glossary: &Glossary,
+                       source: Language, target: Language) -> Result<Vec<Translation>, ApiError> {
+    let sentences: Vec<Vec<SourceSegment>> = documents.iter()
+        .map(|document| translatable_blocks(document, format).iter()
+            .flat_map(|block| split_segments(&block.masked(document), Some(source)).into_iter()
+                .map(|s| {
+                    let range = block.range.start + s.start..block.range.start + s.end;
+                    let (text, markup) = block.protect(document, range.clone());
+                    SourceSegment { range, text: join_lines(&text), markup }
+                })
+                .collect::<Vec<SourceSegment>>())
+            .collect())
+        .collect();
+    let texts: Vec<&str> = sentences.iter().flatten().map(|s| s.text.as_str()).collect();
+    let mut translations = translate_sentences(model, &texts, glossary, source, target)?.into_iter();
+    Ok(documents.iter().zip(sentences)
+        .map(|(document, sentences)| {
+            let segments: Vec<TranslatedSegment> = sentences.iter()
+                .map(|s| TranslatedSegment {
+                    source: s.range.clone(),
+                    translation: restore_markup(&translations.next().unwrap_or_default(), &s.markup),
+                })
+                .collect();
+            Translation { text: assemble_translation(document, &segments), segments }
+        })
+        .collect())
+}
+
+/// Translates sentences in a single model call, protecting the glossary terms.
+fn translate_sentences(model: &TranslationModel, sentences: &[&str], glossary: &Glossary, source: Language,
+                       target: Language) -> Result<Vec<String>, ApiError> {
+    if sentences.is_empty() {
+        return Ok(Vec::new());
     }
+    let protected: Vec<(String, Vec<&str>)> = sentences.iter().map(|s| glossary.protect(s)).collect();
+    let inputs: Vec<&str> = protected.iter().map(|(s, _)| s.as_str()).collect();
+    Ok(model.translate(&inputs, source, target)
+        .map_err(ApiError::inference)?
+        .into_iter()
+        .zip(&protected)
+        .map(|(s, (_, targets))| Glossary::restore(s.trim(), targets))
+        .collect())
+}
+
+/// Replaces the source of each segment in `text` with its translation, keeping the text between them.
+fn assemble_translation(text: &str, segments: &[TranslatedSegment]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for segment in segments {
+        result.push_str(&text[last..segment.source.start]);
+        result.push_str(&segment.translation);
+        last = segment.source.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+pub async fn zero_shot_classification(input: String, split: bool, labels: &[String],
+                                      pool: &InferencePool, registry: web::Data<ModelRegistry>)
+                                      -> Result<SentenceOutputs<Label>, ApiError> {
+    let label_copy: Vec<String> = labels.to_vec();
+    pool.run(move || classify(&input, split, &label_copy, &registry)).await
+}
+
+/// Classifies the input, or each of its sentences if `split` is set. Returns the byte ranges
+/// of the classified sentences in the input with their labels.
+pub(crate) fn classify(input: &str, split: bool, labels: &[String], registry: &ModelRegistry)
+                       -> Result<SentenceOutputs<Label>, ApiError> {
+    let ranges = handle_split(input, split);
+    let splits: Vec<&str> = ranges.iter().map(|r| &input[r.clone()]).collect();
+
+    let sequence_classification_model = registry.zero_shot().map_err(ApiError::model_load)?;
+    let sequence_classification_model = lock_model(&sequence_classification_model);
+
+    let candidate_labels: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    let output = sequence_classification_model.predict_multilabel(
+        &splits,
+        candidate_labels,
+        None,
+        128,
+    );
+    match output {
+        Ok(vecs) => Ok((ranges, vecs)),
+        Err(e) => Err(ApiError::inference(e))
+    }
+}
+
+/// Byte ranges of the sentences of the input if `split` is set, otherwise of the whole input.
+fn handle_split(input: &str, split: bool) -> Vec<Range<usize>> {
+    let sentences = if split {
+        let language = detect_language(input).map(|d| d.language.language());
+        split_sentences(input, language).iter().map(|s| s.start..s.end).collect()
+    } else {
+        Vec::new()
+    };
+    if sentences.is_empty() { std::iter::once(0..input.len()).collect() } else { sentences }
+}
 
-    fn variable_keyword_number_ngram<'a>(how_many: usize, ngram_range: (usize, usize)) -> KeywordExtractionConfig<'a> {
-        let sentence_embeddings_config =
-            SentenceEmbeddingsConfig::from(SentenceEmbeddingsModelType::AllMiniLmL6V2);
+pub(crate) struct KeywordConfigFactory;
 
+impl KeywordConfigFactory {
+    pub(crate) fn variable_keyword_number_ngram<'a>(sentence_embeddings_config: SentenceEmbeddingsConfig,
+                                                    how_many: usize, ngram_range: (usize, usize)) -> KeywordExtractionConfig<'a> {
         KeywordExtractionConfig {
             sentence_embeddings_config,
             tokenizer_stopwords: None,
@@ -154,113 +263,197 @@ impl KeywordConfigFactory {
 }
 
 pub async fn keyword_extraction(
-    request: web::Json<KeywordExtractionRequest>, pool: web::Data<ThreadPool>) ->
-Result<Vec<Vec<Keyword>>, RustBertError> {
-    let input: String = request.orig_text.clone();
-    let split: bool = request.split;
-    let how_many_option: Option<usize> = request.how_many;
-    let ngram_range: Option<(usize, usize)> = request.ngram_range;
-
-    let (tx, rx) = channel();
-    const DEFAULT_HOW_MANY: usize = 5;
-    pool.execute(move || {
-        let vec = handle_split(input, split);
-        let splits: Vec<&str> = vec.iter().map(|s| s.as_str()).collect();
-        let keyword_extraction_model = if how_many_option.is_some() && ngram_range.is_some() {
-            KeywordConfigFactory::variable_keyword_number_ngram(
-                how_many_option.unwrap(), ngram_range.unwrap())
-        } else if how_many_option.is_some() {
-            KeywordConfigFactory::variable_keyword_number(how_many_option.unwrap())
-        } else if ngram_range.is_some() {
-            KeywordConfigFactory::variable_keyword_number_ngram(
-                DEFAULT_HOW_MANY, ngram_range.unwrap())
-        } else {
-            Default::default()
-        };
-        let keyword_extraction_mode_resl =
-            KeywordExtractionModel::new(keyword_extraction_model);
-        match keyword_extraction_mode_resl {
-            Ok(keyword_extraction_model) => {
-                let output_result = keyword_extraction_model.predict(&splits);
-                match output_result {
-                    Ok(output) => {
-                        let _ = tx.send(Ok(output));
-                    }
-                    Err(e) => {
-                        let _ = tx.send(Err(e));
-                    }
-                }
-            }
-            Err(e) => {
-                let _ = tx.send(Err(e));
-            }
-        }
-    });
-    rx.recv().unwrap()
+    request: web::Json<KeywordExtractionRequest>, pool: &InferencePool,
+    registry: web::Data<ModelRegistry>) ->
+Result<SentenceOutputs<Keyword>, ApiError> {
+    pool.run(move || extract_keywords(&request, &registry)).await
 }
 
-pub async fn summarization(input_str: String, model_option: &Option<String>, pool: web::Data<ThreadPool>)
-    -> Result<String, RustBertError> {
-    let model_option_clone = model_option.clone();
-    let (tx, rx) = channel();
-    pool.execute(move || {
-        let config = match model_option_clone {
-            Some(s) => {
-                match s.as_str() {
-                    "distilbart" => SummarizationConfigFactory::distil_bart(),
-                    "pegasus" => SummarizationConfigFactory::pegasus(),
-                    "prophetnet" => SummarizationConfigFactory::prophetnet(),
-                    "long_t5" => SummarizationConfigFactory::long_t5(),
-                    _ => Default::default()
-                }
-            }
-            None => {
-                Default::default()
-            }
-        };
-        let summarization_model_result = SummarizationModel::new(config);
-        match summarization_model_result {
-            Ok(summarization_model) => {
-                let output = summarization_model.summarize(&[input_str]);
-                let _ = tx.send(Ok(output.join(" ").clone()));
-            }
-            Err(e) => {
-                let _ = tx.send(Err(e));
-            }
+/// Extracts the keywords of the input, or of each of its sentences if `split` is set.
+/// Returns the byte ranges of the sentences in the input with their keywords.
+pub(crate) fn extract_keywords(request: &KeywordExtractionRequest, registry: &ModelRegistry)
+                               -> Result<SentenceOutputs<Keyword>, ApiError> {
+    let input = &request.orig_text;
+    let how_many = request.how_many.unwrap_or(DEFAULT_HOW_MANY);
+    let ngram_range = request.ngram_range.unwrap_or(DEFAULT_NGRAM_RANGE);
+    if !(1..=MAX_HOW_MANY).contains(&how_many) {
+        return Err(ApiError::Validation(format!("how_many must be between 1 and {}", MAX_HOW_MANY)));
+    }
+    let (min_ngram, max_ngram) = ngram_range;
+    if min_ngram < 1 || min_ngram > max_ngram || max_ngram > MAX_NGRAM_SIZE {
+        return Err(ApiError::Validation(format!(
+            "ngram_range must be [min, max] with 1 <= min <= max <= {}", MAX_NGRAM_SIZE)));
+    }
+    let ranges = handle_split(input, request.split);
+    let splits: Vec<&str> = ranges.iter().map(|r| &input[r.clone()]).collect();
+    let keyword_extraction_model = registry.keywords().map_err(ApiError::model_load)?;
+    let keyword_extraction_model = lock_model(&keyword_extraction_model);
+    let keywords = rank_keywords(&keyword_extraction_model, &splits, how_many, ngram_range)
+        .map_err(ApiError::inference)?;
+    Ok((ranges, keywords))
+}
+
+/// Ranks the n-grams of each text by the cosine similarity of their embedding to the one of the text,
+/// like `KeywordExtractionModel::predict` but with the number of keywords and n-gram sizes of the call.
+fn rank_keywords(model: &KeywordExtractionModel, texts: &[&str], how_many: usize, ngram_range: (usize, usize))
+                 -> Result<Vec<Vec<Keyword>>, RustBertError> {
+    let words = model.tokenizer.tokenize_list(texts, ngram_range);
+    let text_embeddings = model.sentence_embeddings_model.encode(texts)?;
+    let mut keywords = Vec::with_capacity(texts.len());
+    for (words, text_embedding) in words.iter().zip(&text_embeddings) {
+        let candidates: Vec<&str> = words.keys().map(|word| word.as_ref()).collect();
+        if candidates.is_empty() {
+            keywords.push(Vec::new());
+            continue;
         }
+        let embeddings = model.sentence_embeddings_model.encode(&candidates)?;
+        let mut text_keywords: Vec<Keyword> = candidates.iter().zip(&embeddings)
+            .map(|(word, embedding)| Keyword {
+                text: word.to_string(),
+                score: cosine_similarity(text_embedding, embedding) as f32,
+                offsets: words[*word].clone(),
+            })
+            .collect();
+        text_keywords.sort_by(|a, b| b.score.total_cmp(&a.score));
+        text_keywords.truncate(how_many);
+        keywords.push(text_keywords);
+    }
+    Ok(keywords)
+}
 
-    });
-    rx.recv().unwrap()
+/// Resolves the summarization model requested by a client. Unknown models are rejected,
+/// unless `lenient` is set in which case the default model is used.
+pub(crate) fn summarization_model(model_option: &Option<String>, lenient: bool)
+                                  -> Result<&'static SummarizationModelInfo, ApiError> {
+    match model_option {
+        Some(model) => match SummarizationConfigFactory::find(model) {
+            Some(model) => Ok(model),
+            None if lenient => Ok(SummarizationConfigFactory::default_model()),
+            None => Err(ApiError::UnsupportedModel {
+                value: model.clone(),
+                accepted: SummarizationConfigFactory::models().iter().map(|m| m.name.to_string()).collect(),
+            })
+        },
+        None => Ok(SummarizationConfigFactory::default_model())
+    }
 }
 
-pub async fn dialogue(input_str: String, pool: web::Data<ThreadPool>)
-    -> Result<String, RustBertError> {
-    let (tx, rx) = channel();
-    pool.execute(move || {
-        let conversation_model_res = ConversationModel::new(Default::default());
-        match conversation_model_res {
-            Ok(conversation_model) => {
-                let mut conversation_manager = ConversationManager::new();
-                let conversation_id = conversation_manager.create(input_str.as_str());
-                let map = conversation_model.generate_responses(&mut conversation_manager);
-                let string_list = map.iter().map(|kv| kv.1.to_string()).collect::<Vec<String>>();
-                let _ = tx.send(Ok(string_list.join(" ")));
-            }
-            Err(e) => {
-                let _ = tx.send(Err(e));
-            }
+pub async fn summarization(input_str: String, model_option: &Option<String>, strategy: SummarizationStrategy,
+                           options: GenerationOptions, lenient: bool, pool: &InferencePool,
+                           registry: web::Data<ModelRegistry>) -> Result<String, ApiError> {
+    options.validate()?;
+    let model = summarization_model(model_option, lenient)?;
+    pool.run(move || summarize(&input_str, model, strategy, &options, &registry, &|_| false)).await
+}
+
+/// Summarizes the input with `model`, reporting the fraction of the work done to `progress`.
+/// Fails if `progress` reports that the work was cancelled.
+pub(crate) fn summarize(input: &str, model: &SummarizationModelInfo, strategy: SummarizationStrategy,
+                        options: &GenerationOptions, registry: &ModelRegistry, progress: Progress)
+                        -> Result<String, ApiError> {
+    let summarization_model = registry.summarization(model.name).map_err(ApiError::model_load)?;
+    let summarization_model = lock_model(&summarization_model);
+    summarize_document(&summarization_model, input, strategy, options, model.max_input_tokens, progress)
+        .ok_or_else(|| ApiError::Internal(String::from("The summarization was cancelled")))
+}
+
+/// A sentence of the input picked for an extractive summary.
+pub(crate) struct ExtractedSentence {
+    /// Index of the sentence among all sentences of the input
+    pub(crate) index: usize,
+    pub(crate) range: Range<usize>,
+    pub(crate) score: f64,
+}
+
+pub async fn extractive_summarization(input_str: String, count: usize, pool: &InferencePool,
+                                      registry: web::Data<ModelRegistry>) -> Result<Vec<ExtractedSentence>, ApiError> {
+    pool.run(move || extract_sentences(&input_str, count, &registry)).await
+}
+
+/// Picks the `count` most central sentences of the input, in document order.
+pub(crate) fn extract_sentences(input: &str, count: usize, registry: &ModelRegistry)
+                                -> Result<Vec<ExtractedSentence>, ApiError> {
+    let language = detect_language(input).map(|d| d.language.language());
+    let sentences = split_sentences(input, language);
+    if sentences.is_empty() {
+        return Ok(Vec::new());
+    }
+    if sentences.len() > MAX_INPUT_SENTENCES {
+        return Err(ApiError::Validation(format!(
+            "The text has {} sentences, extractive summaries accept at most {}", sentences.len(), MAX_INPUT_SENTENCES)));
+    }
+    let texts: Vec<&str> = sentences.iter().map(|s| s.text).collect();
+    let model = registry.keywords().map_err(ApiError::model_load)?;
+    let model = lock_model(&model);
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(EMBEDDING_BATCH_SIZE) {
+        embeddings.extend(model.sentence_embeddings_model.encode(batch).map_err(ApiError::inference)?);
+    }
+    let scores = centrality_scores(&embeddings);
+    Ok(top_indices(&scores, count).into_iter()
+        .map(|index| ExtractedSentence {
+            index,
+            range: sentences[index].start..sentences[index].end,
+            score: scores[index],
+        })
+        .collect())
+}
+
+/// Summarizes a document which may exceed the input of the model according to `strategy`.
+/// Returns `None` if `progress` reports that the work was cancelled.
+fn summarize_document(model: &Summarizer, input: &str, strategy: SummarizationStrategy, options: &GenerationOptions,
+                      max_input_tokens: usize, progress: Progress) -> Option<String> {
+    let max_tokens = max_input_tokens.saturating_sub(SPECIAL_TOKENS_MARGIN);
+    let count_tokens = |text: &str| model.get_tokenizer().tokenize(text).len();
+    let language = detect_language(input).map(|d| d.language.language());
+    let chunks = chunk_text(input, language, max_tokens, CHUNK_OVERLAP_TOKENS, count_tokens);
+    if strategy == SummarizationStrategy::Truncate || chunks.len() <= 1 {
+        return Some(model.summarize(&[input], options).join(" "));
+    }
+    println!("Summarizing {} chunks with {:?}", chunks.len(), strategy);
+    if strategy == SummarizationStrategy::Chunk {
+        return summarize_chunks(model, &chunks, options, progress).map(|summaries| summaries.join(" "));
+    }
+    // Upper bound of the passes over the text: the chunks, every reduce round and the final summary
+    let passes = (MAX_REDUCE_ROUNDS + 2) as f32;
+    let mut summary = summarize_chunks(model, &chunks, options, &|done| progress(done / passes))?.join(" ");
+    for round in 1..=MAX_REDUCE_ROUNDS {
+        let chunks = chunk_text(&summary, language, max_tokens, 0, count_tokens);
+        if chunks.len() <= 1 {
+            break;
         }
-    });
-    rx.recv().unwrap()
+        let pass_progress = |done: f32| progress((round as f32 + done) / passes);
+        summary = summarize_chunks(model, &chunks, options, &pass_progress)?.join(" ");
+    }
+    Some(model.summarize(&[summary], options).join(" "))
 }
 
-fn convert_language(language: SupportedLanguage) -> Language {
-    return match language {
-        SupportedLanguage::Fr => Language::French,
-        SupportedLanguage::Hi => Language::Hindi,
-        SupportedLanguage::Pt => Language::Portuguese,
-        SupportedLanguage::En => Language::English,
-        SupportedLanguage::De => Language::German,
-        SupportedLanguage::Nl => Language::Dutch
-    };
+/// Summarizes `chunks` in batches of `SUMMARY_BATCH_SIZE`, reporting the fraction of the chunks
+/// summarized after each batch. Returns `None` if `progress` reports that the work was cancelled.
+fn summarize_chunks(model: &Summarizer, chunks: &[&str], options: &GenerationOptions, progress: Progress)
+                    -> Option<Vec<String>> {
+    let mut summaries = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(SUMMARY_BATCH_SIZE) {
+        summaries.extend(model.summarize(batch, options));
+        if progress(summaries.len() as f32 / chunks.len() as f32) {
+            println!("Summarization cancelled after {} of {} chunks", summaries.len(), chunks.len());
+            return None;
+        }
+    }
+    Some(summaries)
+}
+
+pub async fn dialogue(input_str: String, pool: &InferencePool, registry: web::Data<ModelRegistry>)
+    -> Result<String, ApiError> {
+    pool.run(move || reply(&input_str, &registry)).await
+}
+
+/// Generates the answer of the conversation model to the input.
+pub(crate) fn reply(input: &str, registry: &ModelRegistry) -> Result<String, ApiError> {
+    let conversation_model = registry.dialogue().map_err(ApiError::model_load)?;
+    let mut conversation_manager = ConversationManager::new();
+    conversation_manager.create(input);
+    let map = lock_model(&conversation_model).generate_responses(&mut conversation_manager);
+    let string_list = map.iter().map(|kv| kv.1.to_string()).collect::<Vec<String>>();
+    Ok(string_list.join(" "))
 }
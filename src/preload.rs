@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+use rust_bert::pipelines::conversation::ConversationManager;
+use rust_bert::RustBertError;
+
+use crate::model_cache::catalog;
+use crate::model_files::ModelFiles;
+use crate::model_registry::{lock_model, ModelRegistry};
+use crate::languages::SupportedLanguage;
+use crate::summarization_factory::{GenerationOptions, SummarizationConfigFactory};
+use crate::translation_factory::{TranslationBackend, TranslationDefaults, TranslationModelKey};
+
+const WARM_UP_TEXT: &str = "The weather is nice today. We are going for a walk in the park.";
+
+/// A pipeline and variant to load at startup, written in configuration as
+/// `summarization:<model>`, `translation:<source>-<target>[:<backend>]`, `zero_shot`, `keywords` or `dialogue`.
+/// `sentence_embeddings`, used by extractive summaries, is the model of `keywords`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PreloadTarget {
+    Summarization(String),
+    Translation(SupportedLanguage, SupportedLanguage, Option<TranslationBackend>),
+    ZeroShot,
+    Keywords,
+    Dialogue,
+}
+
+impl FromStr for PreloadTarget {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PreloadTarget, Self::Err> {
+        let (pipeline, variant) = match input.trim().split_once(':') {
+            Some((pipeline, variant)) => (pipeline.trim(), Some(variant.trim())),
+            None => (input.trim(), None)
+        };
+        match (pipeline, variant) {
+            ("summarization", None) => Ok(PreloadTarget::Summarization(
+                SummarizationConfigFactory::default_model().name.to_string())),
+            ("summarization", Some(model)) => SummarizationConfigFactory::find(model)
+                .map(|m| PreloadTarget::Summarization(m.name.to_string()))
+                .ok_or(format!("Unknown summarization model '{}' in '{}'", model, input)),
+            ("translation", Some(variant)) => {
+                let (pair, backend) = match variant.split_once(':') {
+                    Some((pair, backend)) => (pair, Some(backend)),
+                    None => (variant, None)
+                };
+                let (source, target) = pair.split_once('-')
+                    .ok_or(format!("Expected translation:<source>-<target>, got '{}'", input))?;
+                let parse = |l: &str| SupportedLanguage::from_str(l)
+                    .map_err(|_| format!("Unknown language '{}' in '{}'", l, input));
+                let backend = match backend {
+                    Some(b) => Some(TranslationBackend::from_str(b)
+                        .map_err(|_| format!("Unknown translation model '{}' in '{}'", b, input))?),
+                    None => None
+                };
+                Ok(PreloadTarget::Translation(parse(source)?, parse(target)?, backend))
+            }
+            ("zero_shot", None) => Ok(PreloadTarget::ZeroShot),
+            ("keywords", None) | ("sentence_embeddings", None) => Ok(PreloadTarget::Keywords),
+            ("dialogue", None) => Ok(PreloadTarget::Dialogue),
+            _ => Err(format!("Unknown model to preload: '{}'", input))
+        }
+    }
+}
+
+/// Parses the comma separated `preload_models` setting.
+pub(crate) fn parse_preload_targets(setting: &str) -> Result<Vec<PreloadTarget>, String> {
+    setting.split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(PreloadTarget::from_str)
+        .collect()
+}
+
+impl PreloadTarget {
+    /// Pipeline and variant of the model of the target in the catalog of `models list`, or `None`
+    /// for translation pairs without a model.
+    fn catalog_name(&self, translation_defaults: &TranslationDefaults) -> Option<(&'static str, String)> {
+        match self {
+            PreloadTarget::Summarization(variant) => Some(("summarization", variant.clone())),
+            PreloadTarget::Translation(source, target, backend) => {
+                let (source, target) = (source.language(), target.language());
+                let backend = backend.unwrap_or_else(|| translation_defaults.backend_for(source, target));
+                TranslationModelKey::for_pair(backend, source, target).map(|key| ("translation", key.name()))
+            }
+            PreloadTarget::ZeroShot => Some(("zero_shot", String::from("default"))),
+            PreloadTarget::Keywords => Some(("sentence_embeddings", String::from("all-MiniLM-L6-v2"))),
+            PreloadTarget::Dialogue => Some(("dialogue", String::from("default"))),
+        }
+    }
+}
+
+/// Checks that the files of the preloaded models are available, unless they may be downloaded,
+/// so that the server does not start without them. Missing files of the other models are only
+/// logged, as these models fail when first requested.
+pub(crate) fn check_model_files(files: &ModelFiles, targets: &[PreloadTarget],
+                                translation_defaults: &TranslationDefaults) -> Result<(), String> {
+    let preloaded: Vec<(&str, String)> = targets.iter()
+        .filter_map(|target| target.catalog_name(translation_defaults))
+        .collect();
+    let mut errors = Vec::new();
+    for model in catalog() {
+        if let Err(e) = files.check(&format!("{} {}", model.pipeline, model.variant), &model.files) {
+            if preloaded.iter().any(|(pipeline, variant)| *pipeline == model.pipeline && *variant == model.variant) {
+                errors.push(e.to_string());
+            } else {
+                println!("{}", e);
+            }
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(errors.join("\n")) }
+}
+
+/// Loads every target into the registry, optionally running one inference on each so that
+/// lazy initialisation inside libtorch happens before the first user request.
+/// The registry is marked as ready once all of them succeeded.
+pub(crate) fn preload_models(registry: &ModelRegistry, targets: &[PreloadTarget],
+                             translation_defaults: &TranslationDefaults, warm_up: bool)
+    -> Result<(), RustBertError> {
+    for target in targets {
+        println!("Preloading {:?}", target);
+        preload_model(registry, target, translation_defaults, warm_up)?;
+    }
+    registry.mark_ready();
+    println!("All models preloaded");
+    Ok(())
+}
+
+fn preload_model(registry: &ModelRegistry, target: &PreloadTarget, translation_defaults: &TranslationDefaults,
+                 warm_up: bool) -> Result<(), RustBertError> {
+    match target {
+        PreloadTarget::Summarization(variant) => {
+            let model = registry.summarization(variant)?;
+            if warm_up {
+                lock_model(&model).summarize(&[WARM_UP_TEXT], &GenerationOptions::default());
+            }
+        }
+        PreloadTarget::Translation(source, target, backend) => {
+            let (source_language, target_language) = (source.language(), target.language());
+            let backend = backend
+                .unwrap_or_else(|| translation_defaults.backend_for(source_language, target_language));
+            let model = registry.translation(backend, source_language, target_language)?;
+            if warm_up {
+                lock_model(&model).translate(&[WARM_UP_TEXT], source_language, target_language)?;
+            }
+        }
+        PreloadTarget::ZeroShot => {
+            let model = registry.zero_shot()?;
+            if warm_up {
+                lock_model(&model).predict_multilabel([WARM_UP_TEXT], ["weather"], None, 128)?;
+            }
+        }
+        PreloadTarget::Keywords => {
+            let model = registry.keywords()?;
+            if warm_up {
+                lock_model(&model).predict(&[WARM_UP_TEXT])?;
+            }
+        }
+        PreloadTarget::Dialogue => {
+            let model = registry.dialogue()?;
+            if warm_up {
+                let mut conversation_manager = ConversationManager::new();
+                conversation_manager.create(WARM_UP_TEXT);
+                lock_model(&model).generate_responses(&mut conversation_manager);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,367 @@
+use rust_bert::pipelines::translation::Language;
+
+/// Words ending in a period which do not end a sentence, per language, lowercase and without
+/// the final period. Single letters (initials) are handled separately.
+const ABBREVIATIONS: [(Language, &[&str]); 7] = [
+    (Language::English, &[
+        "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "e.g", "i.e", "a.m", "p.m", "u.s", "u.k",
+        "fig", "vol", "approx", "inc", "ltd", "co", "corp", "dept", "est", "jan", "feb", "mar",
+        "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+    ]),
+    (Language::French, &[
+        "mme", "mlle", "mm", "dr", "pr", "st", "ste", "cf", "env", "av", "bd", "p", "pp", "vol", "n°",
+        "janv", "févr", "avr", "juil", "sept", "oct", "nov", "déc",
+    ]),
+    (Language::German, &[
+        "z.b", "bzw", "usw", "ca", "dr", "prof", "nr", "str", "vgl", "d.h", "u.a", "s.o", "s.u", "evtl",
+        "ggf", "inkl", "bspw", "abs", "hr", "fr", "jan", "feb", "märz", "apr", "aug", "sept", "okt", "nov", "dez",
+    ]),
+    (Language::Dutch, &[
+        "dhr", "mevr", "mr", "dr", "prof", "bijv", "blz", "ca", "nr", "o.a", "d.w.z", "m.b.t", "enz", "jl",
+    ]),
+    (Language::Spanish, &[
+        "sr", "sra", "srta", "dr", "dra", "ud", "uds", "prof", "pág", "págs", "núm", "avda", "ej", "aprox",
+        "ene", "feb", "abr", "ago", "sept", "oct", "nov", "dic",
+    ]),
+    (Language::Portuguese, &[
+        "sr", "sra", "srta", "dr", "dra", "prof", "profa", "av", "pág", "núm", "ex", "aprox", "jan", "fev",
+        "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+    ]),
+    (Language::Italian, &[
+        "sig", "sigg", "sig.ra", "dott", "dott.ssa", "prof", "ing", "avv", "ecc", "pag", "es", "ca", "n",
+        "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+    ]),
+];
+
+/// Characters ending a sentence.
+const TERMINATORS: &[char] = &['.', '?', '!', '…', '।', '॥', '。', '！', '？'];
+
+/// Terminators of scripts which do not need whitespace after the end of a sentence.
+const UNSPACED_TERMINATORS: &[char] = &['।', '॥', '。', '！', '？'];
+
+/// Opening quotes and brackets with their closing counterparts.
+const PAIRS: [(char, char); 10] = [
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('“', '”'),
+    ('„', '“'),
+    ('«', '»'),
+    ('「', '」'),
+    ('『', '』'),
+    ('（', '）'),
+];
+
+/// A sentence of a text, with surrounding whitespace removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Sentence<'a> {
+    pub(crate) text: &'a str,
+    /// Byte offset of the sentence in the original text
+    pub(crate) start: usize,
+    /// Byte offset just past the end of the sentence
+    pub(crate) end: usize,
+}
+
+/// Splits `text` into sentences. Sentences end at a terminator followed by whitespace, or at
+/// Devanagari and CJK terminators, unless the terminator is inside quotes or brackets, ends an
+/// abbreviation of `language` (English if unknown) or is followed by a lowercase word.
+/// Quotes and brackets which are not closed in their paragraph, like in `:(`, are ignored.
+/// Blank lines always end a sentence.
+pub(crate) fn split_sentences(text: &str, language: Option<Language>) -> Vec<Sentence<'_>> {
+    let abbreviations = abbreviations(language.unwrap_or(Language::English));
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map(|(offset, _)| *offset).unwrap_or(text.len());
+    let closable = closable_openers(&chars);
+
+    let mut sentences = Vec::new();
+    let mut open: Vec<char> = Vec::new();
+    let mut start = 0;
+    // Byte offset after the last whitespace, quote or bracket, where the current word starts
+    let mut word_start = 0;
+    // Byte offset of the first non-whitespace character since the start of the line or sentence
+    let mut first_text: Option<usize> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        if c == '\n' && starts_blank_line(&chars[i + 1..]) {
+            push_sentence(&mut sentences, text, start, offset(i));
+            start = offset(i);
+            open.clear();
+        } else if TERMINATORS.contains(&c) {
+            let mut end = i + 1;
+            while end < chars.len() && TERMINATORS.contains(&chars[end].1) {
+                end += 1;
+            }
+            let run_end = end;
+            let mut depth = open.len();
+            while end < chars.len() && depth > 0 && closes(open.get(depth - 1), chars[end].1) {
+                depth -= 1;
+                end += 1;
+            }
+            let word_start = word_start.max(start);
+            let at_line_start = first_text.is_none_or(|first| first >= word_start);
+            if depth == 0 && is_boundary(&chars, i, run_end, end, &text[word_start..offset(i)], at_line_start,
+                                         abbreviations) {
+                push_sentence(&mut sentences, text, start, offset(end));
+                start = offset(end);
+                open.clear();
+                first_text = None;
+                i = end;
+                continue;
+            }
+        } else if closes(open.last(), c) {
+            open.pop();
+        } else if closable[i] {
+            open.push(c);
+        }
+        if c == '\n' {
+            first_text = None;
+        } else if !c.is_whitespace() && first_text.is_none() {
+            first_text = Some(offset(i));
+        }
+        if c.is_whitespace() || PAIRS.iter().any(|(opening, _)| *opening == c) {
+            word_start = offset(i) + c.len_utf8();
+        }
+        i += 1;
+    }
+    push_sentence(&mut sentences, text, start, text.len());
+    sentences
+}
+
+/// Splits `text` into paragraphs at blank lines and list items, and those into sentences, which may
+/// span the single line breaks of wrapped text. The text between the sentences holds the original
+/// whitespace and paragraph separators.
+pub(crate) fn split_segments(text: &str, language: Option<Language>) -> Vec<Sentence<'_>> {
+    let mut blocks = Vec::new();
+    let mut block_start = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() || starts_list_item(line) {
+            blocks.push(block_start..offset);
+            block_start = offset;
+        }
+        offset += line.len();
+    }
+    blocks.push(block_start..text.len());
+    blocks.into_iter()
+        .flat_map(|block| split_sentences(&text[block.clone()], language).into_iter()
+            .map(move |s| Sentence { text: s.text, start: s.start + block.start, end: s.end + block.start }))
+        .collect()
+}
+
+/// Joins the lines of a segment with single spaces, as the models expect sentences on one line.
+pub(crate) fn join_lines(text: &str) -> String {
+    text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<&str>>().join(" ")
+}
+
+/// Whether `line` starts with a bullet or a number followed by `.` or `)`.
+fn starts_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let mut rest = line[digits..].chars();
+    let marker = match rest.next() {
+        Some('.' | ')') => digits > 0,
+        Some('-' | '*' | '+' | '•') => digits == 0,
+        _ => false,
+    };
+    marker && rest.next().is_some_and(char::is_whitespace)
+}
+
+fn abbreviations(language: Language) -> &'static [&'static str] {
+    ABBREVIATIONS.iter()
+        .find(|(l, _)| *l == language)
+        .map(|(_, abbreviations)| *abbreviations)
+        .unwrap_or(&[])
+}
+
+fn closes(top: Option<&char>, c: char) -> bool {
+    match top {
+        Some(top) => PAIRS.iter().any(|(opening, closing)| opening == top && *closing == c),
+        None => false
+    }
+}
+
+/// Whether each character is an opening quote or bracket which is closed later in its paragraph,
+/// found in a single backward pass.
+fn closable_openers(chars: &[(usize, char)]) -> Vec<bool> {
+    let mut closable = vec![false; chars.len()];
+    // Whether the closing character of each pair occurs further in the paragraph
+    let mut closers = [false; PAIRS.len()];
+    for i in (0..chars.len()).rev() {
+        let c = chars[i].1;
+        if c == '\n' && starts_blank_line(&chars[i + 1..]) {
+            closers = [false; PAIRS.len()];
+            continue;
+        }
+        closable[i] = PAIRS.iter().zip(&closers).any(|((opening, _), closed)| *opening == c && *closed);
+        for ((_, closing), closed) in PAIRS.iter().zip(closers.iter_mut()) {
+            *closed |= *closing == c;
+        }
+    }
+    closable
+}
+
+fn starts_blank_line(rest: &[(usize, char)]) -> bool {
+    rest.iter()
+        .map(|(_, c)| *c)
+        .take_while(|c| c.is_whitespace())
+        .any(|c| c == '\n')
+}
+
+/// Decides whether the terminators from `terminator` up to `run_end`, followed by closing quotes
+/// and brackets up to `end`, end the sentence. `word` is the text before the terminators, which
+/// is the only text on its line of the sentence if `at_line_start` is set.
+fn is_boundary(chars: &[(usize, char)], terminator: usize, run_end: usize, end: usize, word: &str,
+               at_line_start: bool, abbreviations: &[&str]) -> bool {
+    if run_end == end && chars[terminator..run_end].iter().any(|(_, c)| UNSPACED_TERMINATORS.contains(c)) {
+        return true;
+    }
+    match chars.get(end) {
+        None => return true,
+        Some((_, c)) if !c.is_whitespace() => return false,
+        _ => {}
+    }
+    if let Some((_, next)) = chars[end..].iter().find(|(_, c)| !c.is_whitespace()) {
+        if next.is_lowercase() {
+            return false;
+        }
+    }
+    if run_end - terminator > 1 || chars[terminator].1 != '.' {
+        return true;
+    }
+
+    let word = word.to_lowercase();
+    let is_initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+    let is_list_number = !word.is_empty() && word.chars().all(|c| c.is_ascii_digit()) && at_line_start;
+    !(is_initial || is_list_number || abbreviations.contains(&word.as_str()))
+}
+
+fn push_sentence<'a>(sentences: &mut Vec<Sentence<'a>>, text: &'a str, start: usize, end: usize) {
+    let slice = &text[start..end];
+    let trimmed = slice.trim_start();
+    let start = start + slice.len() - trimmed.len();
+    let trimmed = trimmed.trim_end();
+    if !trimmed.is_empty() {
+        sentences.push(Sentence { text: trimmed, start, end: start + trimmed.len() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text, start and end byte offset of a sentence.
+    type Expected<'a> = (&'a str, usize, usize);
+
+    #[test]
+    fn splits_sentences() {
+        let cases: [(&str, Option<Language>, &str, &[&str]); 27] = [
+            ("empty", None, "", &[]),
+            ("whitespace only", None, " \n\t ", &[]),
+            ("single sentence without terminator", None, "Hello world", &["Hello world"]),
+            ("periods", None, "One. Two. Three.", &["One.", "Two.", "Three."]),
+            ("question and exclamation marks", None, "Really? Yes! Good.", &["Really?", "Yes!", "Good."]),
+            ("repeated terminators", None, "What?! No... Fine.", &["What?!", "No...", "Fine."]),
+            ("decimals", None, "It costs 3.50 euros. Cheap.", &["It costs 3.50 euros.", "Cheap."]),
+            ("domain names", None, "Visit example.com today. Thanks.", &["Visit example.com today.", "Thanks."]),
+            ("english abbreviations", None, "Dr. Smith arrived. He met Mr. Jones.",
+             &["Dr. Smith arrived.", "He met Mr. Jones."]),
+            ("abbreviation with inner periods", None, "Bring fruit, e.g. Apples. Thanks.",
+             &["Bring fruit, e.g. Apples.", "Thanks."]),
+            ("initials", None, "J. R. R. Tolkien wrote books. They sold.",
+             &["J. R. R. Tolkien wrote books.", "They sold."]),
+            ("lowercase continuation", None, "Wait... what happened? Nothing.",
+             &["Wait... what happened?", "Nothing."]),
+            ("german abbreviations", Some(Language::German), "Das ist z.B. Obst. Es schmeckt.",
+             &["Das ist z.B. Obst.", "Es schmeckt."]),
+            ("spanish abbreviations", Some(Language::Spanish), "La Sra. García llegó. Bien.",
+             &["La Sra. García llegó.", "Bien."]),
+            ("abbreviations of other languages", Some(Language::English), "Das ist z.B. Obst.",
+             &["Das ist z.B.", "Obst."]),
+            ("quotes", None, "He said \"Stop! Now.\" Then he left.",
+             &["He said \"Stop! Now.\"", "Then he left."]),
+            ("quote continuing the sentence", None, "\"Stop!\" he said. Then he left.",
+             &["\"Stop!\" he said.", "Then he left."]),
+            ("brackets", None, "This is it (see Fig. 2. And more). Done.",
+             &["This is it (see Fig. 2. And more).", "Done."]),
+            ("german quotes", Some(Language::German), "Er rief „Halt! Jetzt.“ Dann ging er.",
+             &["Er rief „Halt! Jetzt.“", "Dann ging er."]),
+            ("devanagari", Some(Language::Hindi), "मेरा नाम राम है। मैं भारत से हूँ।",
+             &["मेरा नाम राम है।", "मैं भारत से हूँ।"]),
+            ("chinese", Some(Language::ChineseMandarin), "今天天气很好。我们去公园吧！好吗？",
+             &["今天天气很好。", "我们去公园吧！", "好吗？"]),
+            ("japanese quotes", Some(Language::Japanese), "彼は「行こう。」と言った。はい。",
+             &["彼は「行こう。」と言った。", "はい。"]),
+            ("unclosed bracket", None, "He said (see below. Then left. More here. And more.",
+             &["He said (see below.", "Then left.", "More here.", "And more."]),
+            ("emoticon", None, "I lost :( But fine. Next.", &["I lost :( But fine.", "Next."]),
+            ("bracket closed in the next paragraph", None, "Open (here. Now.\n\nThen) more. End.",
+             &["Open (here.", "Now.", "Then) more.", "End."]),
+            ("blank lines", None, "Title\n\nFirst paragraph. Second.", &["Title", "First paragraph.", "Second."]),
+            ("numbered list", None, "1. First item\n2. Second item", &["1. First item\n2. Second item"]),
+        ];
+        for (name, language, input, expected) in cases {
+            let sentences: Vec<&str> = split_sentences(input, language).iter().map(|s| s.text).collect();
+            assert_eq!(sentences, expected, "case '{}'", name);
+        }
+    }
+
+    #[test]
+    fn splits_long_lines_in_one_pass() {
+        // Quadratic scans would take minutes on this single line of numbers, initials and unclosed brackets
+        let text = "He said (see 1. 2. this (a. b. ".repeat(20_000);
+        let sentences = split_sentences(&text, None);
+        assert_eq!(sentences.len(), 20_001);
+        assert_eq!(sentences[1].text, "2. this (a. b. He said (see 1.");
+    }
+
+    #[test]
+    fn splits_segments_by_paragraph() {
+        let cases: [(&str, &[Expected]); 7] = [
+            ("One. Two.", &[("One.", 0, 4), ("Two.", 5, 9)]),
+            ("Title\n\nFirst. Second.\n", &[("Title", 0, 5), ("First.", 7, 13), ("Second.", 14, 21)]),
+            ("- apples\n- pears", &[("- apples", 0, 8), ("- pears", 9, 16)]),
+            ("1. First\n2) Second", &[("1. First", 0, 8), ("2) Second", 9, 18)]),
+            ("A sentence\nwrapped. Next.", &[("A sentence\nwrapped.", 0, 19), ("Next.", 20, 25)]),
+            ("Wrapped\nlines\n \nNew paragraph", &[("Wrapped\nlines", 0, 13), ("New paragraph", 16, 29)]),
+            ("-1 degrees\n*not* a list", &[("-1 degrees\n*not* a list", 0, 23)]),
+        ];
+        for (input, expected) in cases {
+            let segments: Vec<Expected> = split_segments(input, None).iter()
+                .map(|s| (s.text, s.start, s.end))
+                .collect();
+            assert_eq!(segments, expected, "input '{}'", input);
+        }
+    }
+
+    #[test]
+    fn joins_lines() {
+        let cases = [
+            ("One line.", "One line."),
+            ("A sentence\nwrapped.", "A sentence wrapped."),
+            ("Indented  \n   and\r\n wrapped", "Indented and wrapped"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(join_lines(input), expected, "input '{}'", input);
+        }
+    }
+
+    #[test]
+    fn returns_offsets_into_the_original_text() {
+        let cases: [(&str, &[(usize, usize)]); 4] = [
+            ("One. Two.", &[(0, 4), (5, 9)]),
+            ("  Padded.  Text.  ", &[(2, 9), (11, 16)]),
+            ("Ça va? Très bien.", &[(0, 7), (8, 19)]),
+            ("今天很好。我们走。", &[(0, 15), (15, 27)]),
+        ];
+        for (input, expected) in cases {
+            let sentences = split_sentences(input, None);
+            let offsets: Vec<(usize, usize)> = sentences.iter().map(|s| (s.start, s.end)).collect();
+            assert_eq!(offsets, expected, "input '{}'", input);
+            for sentence in sentences {
+                assert_eq!(&input[sentence.start..sentence.end], sentence.text);
+            }
+        }
+    }
+}
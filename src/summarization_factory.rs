@@ -1,53 +1,257 @@
-use rust_bert::pipelines::common::{ModelResource, ModelType};
-use rust_bert::resources::RemoteResource;
+use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+use rust_bert::pipelines::generation_utils::{GenerateOptions, LanguageGenerator};
 use rust_bert::bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources};
-use rust_bert::pipelines::summarization::SummarizationConfig;
+use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationOption};
 use rust_bert::pegasus::{PegasusConfigResources, PegasusModelResources, PegasusVocabResources};
 use rust_bert::prophetnet::{ProphetNetConfigResources, ProphetNetModelResources, ProphetNetVocabResources};
 use rust_bert::longt5::{LongT5ConfigResources, LongT5ModelResources, LongT5VocabResources};
+use rust_bert::pipelines::translation::Language;
+use rust_bert::t5::{T5ConfigResources, T5ModelResources, T5VocabResources};
+use rust_bert::RustBertError;
+use serde::Deserialize;
+
+use crate::errors::ApiError;
+use crate::model_files::{ModelFiles, PretrainedModel};
 
 pub struct SummarizationConfigFactory;
 
+/// Generation settings of a summarization request, overriding those of the model configuration.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GenerationOptions {
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub num_beams: Option<i64>,
+    pub length_penalty: Option<f64>,
+    pub no_repeat_ngram_size: Option<i64>,
+    pub do_sample: Option<bool>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+}
+
+impl GenerationOptions {
+    /// Rejects settings outside of the bounds the models can work with.
+    pub(crate) fn validate(&self) -> Result<(), ApiError> {
+        let mut errors = Vec::new();
+        let mut check = |name: &str, valid: bool, bounds: &str| if !valid {
+            errors.push(format!("{} must be {}", name, bounds));
+        };
+        check("min_length", self.min_length.is_none_or(|v| (0..=1024).contains(&v)), "between 0 and 1024");
+        check("max_length", self.max_length.is_none_or(|v| (1..=1024).contains(&v)), "between 1 and 1024");
+        check("num_beams", self.num_beams.is_none_or(|v| (1..=16).contains(&v)), "between 1 and 16");
+        check("length_penalty", self.length_penalty.is_none_or(|v| (0.0..=5.0).contains(&v)), "between 0 and 5");
+        check("no_repeat_ngram_size", self.no_repeat_ngram_size.is_none_or(|v| (0..=10).contains(&v)),
+              "between 0 and 10");
+        check("temperature", self.temperature.is_none_or(|v| v > 0.0 && v <= 5.0), "above 0 and at most 5");
+        check("top_p", self.top_p.is_none_or(|v| v > 0.0 && v <= 1.0), "above 0 and at most 1");
+        if let (Some(min), Some(max)) = (self.min_length, self.max_length) {
+            check("min_length", min <= max, "at most max_length");
+        }
+        if errors.is_empty() { Ok(()) } else { Err(ApiError::Validation(errors.join(", "))) }
+    }
+
+    /// The settings as options of a single generation call, falling back to the model configuration
+    /// for those which are not set.
+    fn generate_options(&self) -> GenerateOptions<'static> {
+        GenerateOptions {
+            min_length: self.min_length,
+            max_length: self.max_length,
+            num_beams: self.num_beams,
+            length_penalty: self.length_penalty,
+            no_repeat_ngram_size: self.no_repeat_ngram_size,
+            do_sample: self.do_sample,
+            temperature: self.temperature,
+            top_p: self.top_p,
+            ..Default::default()
+        }
+    }
+}
+
+/// A summarization model taking its generation settings per call, so that a single instance
+/// serves requests with different settings.
+pub struct Summarizer {
+    model: SummarizationOption,
+}
+
+impl Summarizer {
+    pub fn new(config: SummarizationConfig) -> Result<Self, RustBertError> {
+        Ok(Summarizer { model: SummarizationOption::new(config)? })
+    }
+
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Summarizes each of `texts` with the settings of `options`.
+    pub fn summarize<S: AsRef<str>>(&self, texts: &[S], options: &GenerationOptions) -> Vec<String> {
+        // T5 is a multi-task model which has to be told to summarize
+        let prefix = if self.model.model_type() == ModelType::T5 { "summarize: " } else { "" };
+        let texts: Vec<String> = texts.iter().map(|text| format!("{}{}", prefix, text.as_ref())).collect();
+        let texts = Some(texts.as_slice());
+        let options = Some(options.generate_options());
+        let outputs = match &self.model {
+            SummarizationOption::Bart(model) => model.generate(texts, options),
+            SummarizationOption::T5(model) => model.generate(texts, options),
+            SummarizationOption::LongT5(model) => model.generate(texts, options),
+            SummarizationOption::ProphetNet(model) => model.generate(texts, options),
+            SummarizationOption::Pegasus(model) => model.generate(texts, options),
+        };
+        outputs.into_iter().map(|output| output.text).collect()
+    }
+}
+
+/// A summarization model clients can request by name.
+pub struct SummarizationModelInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Maximum number of input tokens; longer inputs are truncated by the model
+    pub max_input_tokens: usize,
+    /// Language of the texts the model was trained on
+    pub language: Language,
+    model_type: ModelType,
+    pub(crate) files: PretrainedModel,
+}
+
+impl SummarizationModelInfo {
+    /// Configuration of the model, reading its files from `model_dir` where present.
+    pub(crate) fn config(&self, files: &ModelFiles) -> Result<SummarizationConfig, RustBertError> {
+        let resources = files.model(self.name, &self.files)?;
+        Ok(SummarizationConfig::new(self.model_type, resources.model, resources.config, resources.vocab,
+                                    resources.merges))
+    }
+}
+
+/// The available summarization models, the default one first.
+const MODELS: [SummarizationModelInfo; 9] = [
+    SummarizationModelInfo {
+        name: "bart_large_cnn",
+        description: "BART large fine-tuned on CNN/DailyMail news, multi-sentence summaries",
+        max_input_tokens: 1024,
+        language: Language::English,
+        model_type: ModelType::Bart,
+        files: PretrainedModel {
+            model: BartModelResources::BART_CNN,
+            config: BartConfigResources::BART_CNN,
+            vocab: BartVocabResources::BART_CNN,
+            merges: Some(BartMergesResources::BART_CNN),
+        },
+    },
+    SummarizationModelInfo {
+        name: "distilbart",
+        description: "DistilBART 6-6 fine-tuned on CNN/DailyMail news, faster than BART large",
+        max_input_tokens: 1024,
+        language: Language::English,
+        model_type: ModelType::Bart,
+        files: PretrainedModel {
+            model: BartModelResources::DISTILBART_CNN_6_6,
+            config: BartConfigResources::DISTILBART_CNN_6_6,
+            vocab: BartVocabResources::DISTILBART_CNN_6_6,
+            merges: Some(BartMergesResources::DISTILBART_CNN_6_6),
+        },
+    },
+    SummarizationModelInfo {
+        name: "distilbart_12_6",
+        description: "DistilBART 12-6 fine-tuned on CNN/DailyMail news, between DistilBART 6-6 and BART large",
+        max_input_tokens: 1024,
+        language: Language::English,
+        model_type: ModelType::Bart,
+        files: PretrainedModel {
+            model: BartModelResources::DISTILBART_CNN_12_6,
+            config: BartConfigResources::DISTILBART_CNN_12_6,
+            vocab: BartVocabResources::DISTILBART_CNN_12_6,
+            merges: Some(BartMergesResources::DISTILBART_CNN_12_6),
+        },
+    },
+    SummarizationModelInfo {
+        name: "bart_large_xsum",
+        description: "BART large fine-tuned on XSum news, single sentence summaries",
+        max_input_tokens: 1024,
+        language: Language::English,
+        model_type: ModelType::Bart,
+        files: PretrainedModel {
+            model: BartModelResources::BART_XSUM,
+            config: BartConfigResources::BART_XSUM,
+            vocab: BartVocabResources::BART_XSUM,
+            merges: Some(BartMergesResources::BART_XSUM),
+        },
+    },
+    SummarizationModelInfo {
+        name: "pegasus",
+        description: "Pegasus fine-tuned on CNN/DailyMail news",
+        max_input_tokens: 1024,
+        language: Language::English,
+        model_type: ModelType::Pegasus,
+        files: PretrainedModel {
+            model: PegasusModelResources::CNN_DAILYMAIL,
+            config: PegasusConfigResources::CNN_DAILYMAIL,
+            vocab: PegasusVocabResources::CNN_DAILYMAIL,
+            merges: None,
+        },
+    },
+    SummarizationModelInfo {
+        name: "prophetnet",
+        description: "ProphetNet large, uncased, fine-tuned on CNN/DailyMail news",
+        max_input_tokens: 512,
+        language: Language::English,
+        model_type: ModelType::ProphetNet,
+        files: PretrainedModel {
+            model: ProphetNetModelResources::PROPHETNET_LARGE_UNCASED,
+            config: ProphetNetConfigResources::PROPHETNET_LARGE_UNCASED,
+            vocab: ProphetNetVocabResources::PROPHETNET_LARGE_UNCASED,
+            merges: None,
+        },
+    },
+    SummarizationModelInfo {
+        name: "long_t5",
+        description: "LongT5 with transient global attention fine-tuned on book summaries, for long documents",
+        max_input_tokens: 16384,
+        language: Language::English,
+        model_type: ModelType::LongT5,
+        files: PretrainedModel {
+            model: LongT5ModelResources::TGLOBAL_BASE_BOOK_SUMMARY,
+            config: LongT5ConfigResources::TGLOBAL_BASE_BOOK_SUMMARY,
+            vocab: LongT5VocabResources::TGLOBAL_BASE_BOOK_SUMMARY,
+            merges: None,
+        },
+    },
+    SummarizationModelInfo {
+        name: "t5_small",
+        description: "T5 small, multi-task model prompted to summarize, the smallest and fastest model",
+        max_input_tokens: 512,
+        language: Language::English,
+        model_type: ModelType::T5,
+        files: PretrainedModel {
+            model: T5ModelResources::T5_SMALL,
+            config: T5ConfigResources::T5_SMALL,
+            vocab: T5VocabResources::T5_SMALL,
+            merges: None,
+        },
+    },
+    SummarizationModelInfo {
+        name: "t5_base",
+        description: "T5 base, multi-task model prompted to summarize",
+        max_input_tokens: 512,
+        language: Language::English,
+        model_type: ModelType::T5,
+        files: PretrainedModel {
+            model: T5ModelResources::T5_BASE,
+            config: T5ConfigResources::T5_BASE,
+            vocab: T5VocabResources::T5_BASE,
+            merges: None,
+        },
+    },
+];
+
 impl SummarizationConfigFactory {
-    pub fn distil_bart() -> SummarizationConfig {
-        SummarizationConfig::new(
-            ModelType::Bart,
-            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
-                BartModelResources::DISTILBART_CNN_6_6,
-            ))),
-            RemoteResource::from_pretrained(BartConfigResources::DISTILBART_CNN_6_6),
-            RemoteResource::from_pretrained(BartVocabResources::DISTILBART_CNN_6_6),
-            Some(RemoteResource::from_pretrained(BartMergesResources::DISTILBART_CNN_6_6)),
-        )
-    }
-    pub fn pegasus() -> SummarizationConfig {
-        SummarizationConfig::new(
-            ModelType::Pegasus,
-            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
-                PegasusModelResources::CNN_DAILYMAIL,
-            ))),
-            RemoteResource::from_pretrained(PegasusConfigResources::CNN_DAILYMAIL),
-            RemoteResource::from_pretrained(PegasusVocabResources::CNN_DAILYMAIL),
-            None)
-    }
-    pub fn prophetnet() -> SummarizationConfig {
-        SummarizationConfig::new(
-            ModelType::ProphetNet,
-            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
-                ProphetNetModelResources::PROPHETNET_LARGE_UNCASED,
-            ))),
-            RemoteResource::from_pretrained(ProphetNetConfigResources::PROPHETNET_LARGE_UNCASED),
-            RemoteResource::from_pretrained(ProphetNetVocabResources::PROPHETNET_LARGE_UNCASED),
-            None)
-    }
-    pub fn long_t5() -> SummarizationConfig {
-        SummarizationConfig::new(
-            ModelType::LongT5,
-            ModelResource::Torch(Box::new(RemoteResource::from_pretrained(
-                LongT5ModelResources::TGLOBAL_BASE_BOOK_SUMMARY,
-            ))),
-            RemoteResource::from_pretrained(LongT5ConfigResources::TGLOBAL_BASE_BOOK_SUMMARY),
-            RemoteResource::from_pretrained(LongT5VocabResources::TGLOBAL_BASE_BOOK_SUMMARY),
-            None)
-    }
-}
\ No newline at end of file
+    pub fn default_model() -> &'static SummarizationModelInfo {
+        &MODELS[0]
+    }
+
+    pub fn models() -> &'static [SummarizationModelInfo] {
+        &MODELS
+    }
+
+    /// The model registered under `name`, or `None` if there is no such model.
+    pub fn find(name: &str) -> Option<&'static SummarizationModelInfo> {
+        MODELS.iter().find(|m| m.name == name)
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rust_bert::m2m_100::{M2M100ConfigResources, M2M100MergesResources, M2M100ModelResources, M2M100SourceLanguages,
+                         M2M100TargetLanguages, M2M100VocabResources};
+use rust_bert::marian::{MarianConfigResources, MarianModelResources, MarianSourceLanguages, MarianSpmResources,
+                        MarianTargetLanguages, MarianVocabResources};
+use rust_bert::mbart::{MBartConfigResources, MBartModelResources, MBartSourceLanguages, MBartTargetLanguages,
+                       MBartVocabResources};
+use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::translation::{Language, TranslationConfig, TranslationModel};
+use rust_bert::RustBertError;
+
+use crate::errors::ApiError;
+use crate::languages::SupportedLanguage;
+use crate::model_files::{ModelFiles, PretrainedModel};
+
+/// A pretrained Marian model: its source languages, target languages and files.
+macro_rules! marian_model {
+    ($name:ident) => {
+        (
+            &MarianSourceLanguages::$name,
+            &MarianTargetLanguages::$name,
+            PretrainedModel {
+                model: MarianModelResources::$name,
+                config: MarianConfigResources::$name,
+                vocab: MarianVocabResources::$name,
+                merges: Some(MarianSpmResources::$name),
+            },
+        )
+    };
+}
+
+/// The pretrained Marian models, as (source languages, target languages, files).
+const MARIAN_MODELS: [(&[Language], &[Language], PretrainedModel); 20] = [
+    marian_model!(ENGLISH2ROMANCE),
+    marian_model!(ENGLISH2GERMAN),
+    marian_model!(ENGLISH2RUSSIAN),
+    marian_model!(ENGLISH2DUTCH),
+    marian_model!(ENGLISH2CHINESE),
+    marian_model!(ENGLISH2SWEDISH),
+    marian_model!(ENGLISH2ARABIC),
+    marian_model!(ENGLISH2HINDI),
+    marian_model!(ENGLISH2HEBREW),
+    marian_model!(ROMANCE2ENGLISH),
+    marian_model!(GERMAN2ENGLISH),
+    marian_model!(RUSSIAN2ENGLISH),
+    marian_model!(DUTCH2ENGLISH),
+    marian_model!(CHINESE2ENGLISH),
+    marian_model!(SWEDISH2ENGLISH),
+    marian_model!(ARABIC2ENGLISH),
+    marian_model!(HINDI2ENGLISH),
+    marian_model!(HEBREW2ENGLISH),
+    marian_model!(FRENCH2GERMAN),
+    marian_model!(GERMAN2FRENCH),
+];
+
+const M2M100_MODEL: PretrainedModel = PretrainedModel {
+    model: M2M100ModelResources::M2M100_418M,
+    config: M2M100ConfigResources::M2M100_418M,
+    vocab: M2M100VocabResources::M2M100_418M,
+    merges: Some(M2M100MergesResources::M2M100_418M),
+};
+
+/// MBart50 has a single sentencepiece file serving as vocabulary and merges.
+const MBART50_MODEL: PretrainedModel = PretrainedModel {
+    model: MBartModelResources::MBART50_MANY_TO_MANY,
+    config: MBartConfigResources::MBART50_MANY_TO_MANY,
+    vocab: MBartVocabResources::MBART50_MANY_TO_MANY,
+    merges: Some(MBartVocabResources::MBART50_MANY_TO_MANY),
+};
+
+/// Model family used to translate a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranslationBackend {
+    /// Small single pair Helsinki-NLP models, only available for some pairs
+    Marian,
+    /// Many-to-many model covering every supported language
+    M2M100,
+    /// Many-to-many model covering 50 languages
+    MBart50,
+}
+
+impl FromStr for TranslationBackend {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<TranslationBackend, Self::Err> {
+        match input {
+            "marian" => Ok(TranslationBackend::Marian),
+            "m2m100" => Ok(TranslationBackend::M2M100),
+            "mbart50" => Ok(TranslationBackend::MBart50),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TranslationBackend {
+    pub const ALL: [TranslationBackend; 3] = [
+        TranslationBackend::Marian,
+        TranslationBackend::M2M100,
+        TranslationBackend::MBart50,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TranslationBackend::Marian => "marian",
+            TranslationBackend::M2M100 => "m2m100",
+            TranslationBackend::MBart50 => "mbart50",
+        }
+    }
+
+    pub fn supports(&self, source: Language, target: Language) -> bool {
+        match self {
+            TranslationBackend::Marian => MARIAN_MODELS.iter()
+                .any(|(sources, targets, _)| sources.contains(&source) && targets.contains(&target)),
+            TranslationBackend::M2M100 => source != target
+                && SupportedLanguage::from_language(source).is_some()
+                && SupportedLanguage::from_language(target).is_some(),
+            TranslationBackend::MBart50 => source != target
+                && MBartSourceLanguages::MBART50_MANY_TO_MANY.contains(&source)
+                && MBartTargetLanguages::MBART50_MANY_TO_MANY.contains(&target),
+        }
+    }
+}
+
+/// Identifies a translation model instance. Marian models cover fixed language pairs, while
+/// the many-to-many backends use a single model for every pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranslationModelKey {
+    /// Index into the pretrained Marian models
+    Marian(usize),
+    ManyToMany(TranslationBackend),
+}
+
+impl TranslationModelKey {
+    /// Returns the model of `backend` translating from `source` to `target`,
+    /// or `None` if there is no Marian model for the pair.
+    pub fn for_pair(backend: TranslationBackend, source: Language, target: Language) -> Option<TranslationModelKey> {
+        match backend {
+            TranslationBackend::Marian => MARIAN_MODELS.iter()
+                .position(|(sources, targets, _)| sources.contains(&source) && targets.contains(&target))
+                .map(TranslationModelKey::Marian),
+            backend => Some(TranslationModelKey::ManyToMany(backend)),
+        }
+    }
+
+    /// Every translation model, Marian models first.
+    pub fn all() -> impl Iterator<Item=TranslationModelKey> {
+        (0..MARIAN_MODELS.len()).map(TranslationModelKey::Marian)
+            .chain([TranslationBackend::M2M100, TranslationBackend::MBart50].map(TranslationModelKey::ManyToMany))
+    }
+
+    /// Name of the model, e.g. `marian:en-fr+es+it+ca+ro+pt+oc` or `m2m100`.
+    pub fn name(&self) -> String {
+        match self {
+            TranslationModelKey::Marian(index) => {
+                let (sources, targets, _) = MARIAN_MODELS[*index];
+                let codes = |languages: &[Language]| languages.iter()
+                    .map(|l| l.get_iso_639_1_code().unwrap_or(l.get_iso_639_3_code()))
+                    .collect::<Vec<&str>>()
+                    .join("+");
+                format!("marian:{}-{}", codes(sources), codes(targets))
+            }
+            TranslationModelKey::ManyToMany(backend) => backend.name().to_string(),
+        }
+    }
+}
+
+pub struct TranslationModelFactory;
+
+impl TranslationModelFactory {
+    pub(crate) fn create(key: TranslationModelKey, files: &ModelFiles) -> Result<TranslationModel, RustBertError> {
+        let (model_type, sources, targets): (_, &[Language], &[Language]) = match key {
+            TranslationModelKey::Marian(index) => (ModelType::Marian, MARIAN_MODELS[index].0, MARIAN_MODELS[index].1),
+            TranslationModelKey::ManyToMany(TranslationBackend::MBart50) => (ModelType::MBart,
+                &MBartSourceLanguages::MBART50_MANY_TO_MANY, &MBartTargetLanguages::MBART50_MANY_TO_MANY),
+            TranslationModelKey::ManyToMany(_) => (ModelType::M2M100,
+                &M2M100SourceLanguages::M2M100_418M, &M2M100TargetLanguages::M2M100_418M),
+        };
+        let resources = files.model(&key.name(), &Self::files(key))?;
+        TranslationModel::new(TranslationConfig::new(
+            model_type, resources.model, resources.config, resources.vocab, resources.merges, sources, targets, None))
+    }
+
+    /// Files of the model behind `key`.
+    pub(crate) fn files(key: TranslationModelKey) -> PretrainedModel {
+        match key {
+            TranslationModelKey::Marian(index) => MARIAN_MODELS[index].2,
+            TranslationModelKey::ManyToMany(TranslationBackend::MBart50) => MBART50_MODEL,
+            TranslationModelKey::ManyToMany(_) => M2M100_MODEL,
+        }
+    }
+}
+
+/// Backend used per language pair when a request does not choose one, configured through
+/// the `translation_models` setting as `<source>-<target>:<backend>` entries separated by commas.
+/// Pairs without an entry use Marian where available and M2M100 otherwise.
+#[derive(Debug, Default, Clone)]
+pub struct TranslationDefaults {
+    pairs: HashMap<(Language, Language), TranslationBackend>,
+}
+
+impl FromStr for TranslationDefaults {
+    type Err = String;
+
+    fn from_str(setting: &str) -> Result<TranslationDefaults, Self::Err> {
+        let mut pairs = HashMap::new();
+        for entry in setting.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            let (pair, backend) = entry.split_once(':')
+                .ok_or(format!("Expected <source>-<target>:<backend>, got '{}'", entry))?;
+            let (source, target) = pair.split_once('-')
+                .ok_or(format!("Expected <source>-<target>:<backend>, got '{}'", entry))?;
+            let parse = |l: &str| SupportedLanguage::from_str(l)
+                .map(|l| l.language())
+                .map_err(|_| format!("Unknown language '{}' in '{}'", l, entry));
+            let (source, target) = (parse(source)?, parse(target)?);
+            let backend = TranslationBackend::from_str(backend.trim())
+                .map_err(|_| format!("Unknown translation model '{}' in '{}'", backend, entry))?;
+            if !backend.supports(source, target) {
+                return Err(format!("Translation model {} does not support '{}'", backend.name(), pair));
+            }
+            pairs.insert((source, target), backend);
+        }
+        Ok(TranslationDefaults { pairs })
+    }
+}
+
+impl TranslationDefaults {
+    /// Resolves the backend requested by a client for a language pair. Unknown backends and
+    /// backends which cannot translate the pair are rejected, unless `lenient` is set in which
+    /// case the default backend of the pair is used.
+    pub(crate) fn resolve(&self, model: Option<&str>, source: Language, target: Language, lenient: bool)
+        -> Result<TranslationBackend, ApiError> {
+        let default = self.backend_for(source, target);
+        match model.map(|m| (m, TranslationBackend::from_str(m))) {
+            None => Ok(default),
+            Some((_, Ok(backend))) if backend.supports(source, target) => Ok(backend),
+            Some(_) if lenient => Ok(default),
+            Some((m, _)) => Err(ApiError::UnsupportedModel {
+                value: m.to_string(),
+                accepted: TranslationBackend::ALL.iter()
+                    .filter(|b| b.supports(source, target))
+                    .map(|b| b.name().to_string())
+                    .collect(),
+            })
+        }
+    }
+
+    pub fn backend_for(&self, source: Language, target: Language) -> TranslationBackend {
+        match self.pairs.get(&(source, target)) {
+            Some(backend) => *backend,
+            None if TranslationBackend::Marian.supports(source, target) => TranslationBackend::Marian,
+            None => TranslationBackend::M2M100,
+        }
+    }
+}
@@ -1,25 +1,87 @@
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
 use rust_bert::pipelines::sequence_classification::Label;
+use std::collections::{BTreeMap, HashMap};
+use crate::chunking::SummarizationStrategy;
+use crate::extractive::SummarizationMode;
+use crate::jobs::JobState;
+use crate::model_cache::{Checksum, FileLocation};
+use crate::markup::TextFormat;
+use crate::model_registry::ModelState;
+use crate::summarization_factory::GenerationOptions;
 
 pub(crate) struct ErrorCodes;
 
 impl ErrorCodes {
     pub const STATUS_OK: &'static str = "OK";
     pub const STATUS_FAILED: &'static str = "Failed";
+
+    pub const VALIDATION_ERROR: &'static str = "VALIDATION_ERROR";
+    pub const UNSUPPORTED_LANGUAGE: &'static str = "UNSUPPORTED_LANGUAGE";
+    pub const LANGUAGE_NOT_DETECTED: &'static str = "LANGUAGE_NOT_DETECTED";
+    pub const UNSUPPORTED_MODEL: &'static str = "UNSUPPORTED_MODEL";
+    pub const MODEL_LOAD_FAILED: &'static str = "MODEL_LOAD_FAILED";
+    pub const INFERENCE_FAILED: &'static str = "INFERENCE_FAILED";
+    pub const INTERNAL_ERROR: &'static str = "INTERNAL_ERROR";
+    pub const NOT_FOUND: &'static str = "NOT_FOUND";
+    pub const UNAUTHORIZED: &'static str = "UNAUTHORIZED";
+    pub const QUEUE_FULL: &'static str = "QUEUE_FULL";
+    pub const TIMEOUT: &'static str = "TIMEOUT";
 }
 
 #[derive(Deserialize)]
 pub(crate) struct TranslationRequest {
     pub(crate) orig_text: String,
     pub(crate) language: String,
-    pub(crate) source_language: Option<String>
+    pub(crate) source_language: Option<String>,
+    /// Translation backend: `marian`, `m2m100` or `mbart50`. Defaults depend on the language pair
+    pub(crate) model: Option<String>,
+    /// Falls back to English for unknown or undetected languages and to the default model for unknown models
+    /// instead of rejecting the request
+    #[serde(default)]
+    pub(crate) lenient: bool,
+    /// Format of `orig_text`; only the text of HTML and Markdown documents is translated
+    #[serde(default)]
+    pub(crate) format: TextFormat,
+    /// Source terms with the translation to use for them
+    #[serde(default)]
+    pub(crate) glossary: HashMap<String, String>,
+    /// Terms to keep untranslated
+    #[serde(default)]
+    pub(crate) do_not_translate: Vec<String>,
+    /// Names of the glossaries loaded at startup to apply, before `glossary` and `do_not_translate`
+    #[serde(default)]
+    pub(crate) glossaries: Vec<String>,
+    /// Milliseconds to wait for the result, instead of the `inference_timeout_ms` setting
+    pub(crate) timeout_ms: Option<u64>
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BatchTranslationRequest {
+    pub(crate) texts: Vec<String>,
+    /// Target languages, every text is translated into each of them
+    pub(crate) languages: Vec<String>,
+    pub(crate) source_language: Option<String>,
+    pub(crate) model: Option<String>,
+    #[serde(default)]
+    pub(crate) lenient: bool,
+    #[serde(default)]
+    pub(crate) format: TextFormat,
+    #[serde(default)]
+    pub(crate) glossary: HashMap<String, String>,
+    #[serde(default)]
+    pub(crate) do_not_translate: Vec<String>,
+    #[serde(default)]
+    pub(crate) glossaries: Vec<String>,
+    pub(crate) timeout_ms: Option<u64>
 }
 
 #[derive(Deserialize)]
 pub(crate) struct ZeroShotRequest {
     pub(crate) orig_text: String,
     pub(crate) split: bool,
-    pub(crate) labels: Option<Vec<String>>
+    pub(crate) labels: Option<Vec<String>>,
+    pub(crate) timeout_ms: Option<u64>
 }
 
 #[derive(Deserialize)]
@@ -27,20 +89,86 @@ pub struct KeywordExtractionRequest {
     pub orig_text: String,
     pub split: bool,
     pub how_many: Option<usize>,
-    pub ngram_range: Option<(usize, usize)>
+    pub ngram_range: Option<(usize, usize)>,
+    pub timeout_ms: Option<u64>
 }
 
 #[derive(Serialize)]
 pub(crate) struct ZeroShotResponse {
     pub(crate) sentences: Vec<String>,
-    pub(crate) responses: Vec<Vec<Label>>,
+    /// Position of each sentence in `orig_text`
+    pub(crate) spans: Vec<TextSpan>,
+    pub(crate) responses: Vec<Vec<ZeroShotLabel>>,
     pub(crate) status: String
 }
 
+#[derive(Serialize)]
+pub(crate) struct ZeroShotLabel {
+    #[serde(flatten)]
+    pub(crate) label: Label,
+    /// Position of the classified sentence in `orig_text`
+    pub(crate) span: TextSpan
+}
+
+/// Position of a span of `orig_text`, as character and UTF-8 byte offsets. Ends are exclusive.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextSpan {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) byte_start: usize,
+    pub(crate) byte_end: usize
+}
+
+impl TextSpan {
+    pub(crate) fn from_bytes(text: &str, byte_start: usize, byte_end: usize) -> TextSpan {
+        let start = text[..byte_start].chars().count();
+        TextSpan {
+            start,
+            end: start + text[byte_start..byte_end].chars().count(),
+            byte_start,
+            byte_end
+        }
+    }
+}
+
 #[derive(Deserialize,Serialize)]
 pub(crate) struct TranslationResponse {
     pub(crate) orig_text: String,
-    pub(crate) translation: String
+    pub(crate) translation: String,
+    /// Translation backend used
+    #[serde(default)]
+    pub(crate) model: String,
+    /// Source language detected when the request did not specify one
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) detected_language: Option<String>,
+    /// Confidence of the detected source language, between 0 and 1
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) detection_confidence: Option<f64>,
+    /// Source sentences aligned with their translations
+    #[serde(default)]
+    pub(crate) segments: Vec<TranslationSegment>
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TranslationSegment {
+    pub(crate) index: usize,
+    pub(crate) source: String,
+    pub(crate) translation: String,
+    /// Position of `source` in `orig_text`
+    pub(crate) span: TextSpan
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BatchTranslationResponse {
+    pub(crate) texts: Vec<String>,
+    /// Translations of the texts, in request order, per target language
+    pub(crate) translations: BTreeMap<String, Vec<String>>,
+    /// Translation model used per target language
+    pub(crate) models: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) detected_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) detection_confidence: Option<f64>
 }
 
 #[derive(Serialize)]
@@ -48,6 +176,7 @@ pub(crate) struct Info {
     pub(crate) message: String,
     pub(crate) timestamp: String,
     pub(crate) server_address: String,
+    pub(crate) ready: bool,
 }
 
 #[derive(Serialize)]
@@ -62,17 +191,35 @@ pub(crate) struct ExtractionKeyword {
     pub(crate) text: String,
     /// Similarity score for the keyword
     pub(crate) score: f32,
+    /// Occurrences of the keyword in `orig_text`
+    pub(crate) offsets: Vec<TextSpan>,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct SummarizationRequest {
     pub(crate) orig_text: String,
-    pub(crate) model: Option<String>
+    pub(crate) model: Option<String>,
+    /// Falls back to the default model for unknown model names instead of rejecting the request
+    #[serde(default)]
+    pub(crate) lenient: bool,
+    /// How to summarize texts longer than the input of the model
+    #[serde(default)]
+    pub(crate) strategy: SummarizationStrategy,
+    #[serde(flatten)]
+    pub(crate) generation: GenerationOptions,
+    /// `abstractive` summaries are generated by `model`, `extractive` ones pick sentences of `orig_text`
+    #[serde(default)]
+    pub(crate) mode: SummarizationMode,
+    /// Number of sentences of extractive summaries
+    pub(crate) sentences: Option<usize>,
+    /// Milliseconds to wait for the result, instead of the `inference_timeout_ms` setting
+    pub(crate) timeout_ms: Option<u64>
 }
 
 #[derive(Deserialize)]
 pub(crate) struct DialogueRequest {
-    pub(crate) question: String
+    pub(crate) question: String,
+    pub(crate) timeout_ms: Option<u64>
 }
 
 #[derive(Serialize)]
@@ -80,3 +227,158 @@ pub(crate) struct SimpleTextResponse {
     pub(crate) text: String,
     pub(crate) status: String
 }
+
+#[derive(Serialize)]
+pub(crate) struct SummarizationModelsResponse {
+    pub(crate) models: Vec<SummarizationModelDescription>
+}
+
+#[derive(Serialize)]
+pub(crate) struct SummarizationModelDescription {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) max_input_tokens: usize,
+    /// ISO 639-1 code of the language of the texts the model was trained on
+    pub(crate) language: String,
+    /// Whether the model is used when requests do not name one
+    pub(crate) default: bool
+}
+
+#[derive(Serialize)]
+pub(crate) struct ModelFilesResponse {
+    pub(crate) models: Vec<ModelFilesStatus>
+}
+
+#[derive(Serialize)]
+pub(crate) struct ModelFilesStatus {
+    pub(crate) pipeline: String,
+    pub(crate) variant: String,
+    /// Whether every file of the model is available without downloading
+    pub(crate) complete: bool,
+    /// Total size in bytes of the available files
+    pub(crate) size: u64,
+    pub(crate) files: Vec<FileStatus>
+}
+
+#[derive(Serialize)]
+pub(crate) struct FileStatus {
+    /// Name of the file in rust-bert, e.g. `bart-cnn/vocab`
+    pub(crate) name: String,
+    pub(crate) url: String,
+    pub(crate) location: FileLocation,
+    pub(crate) path: Option<String>,
+    pub(crate) size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sha256: Option<String>,
+    /// SHA-256 recorded when `models fetch` downloaded the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) expected_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) checksum: Option<Checksum>
+}
+
+#[derive(Serialize)]
+pub(crate) struct ExtractiveSummaryResponse {
+    /// The picked sentences joined by spaces
+    pub(crate) text: String,
+    pub(crate) sentences: Vec<SummarySentence>,
+    pub(crate) status: String
+}
+
+#[derive(Serialize)]
+pub(crate) struct SummarySentence {
+    /// Index of the sentence among all sentences of `orig_text`
+    pub(crate) index: usize,
+    pub(crate) text: String,
+    /// Centrality of the sentence in the document
+    pub(crate) score: f64,
+    pub(crate) span: TextSpan
+}
+
+#[derive(Serialize)]
+pub(crate) struct ModelStatus {
+    pub(crate) variant: String,
+    #[serde(flatten)]
+    pub(crate) state: ModelState
+}
+
+#[derive(Serialize)]
+pub(crate) struct HealthResponse {
+    pub(crate) status: String,
+    pub(crate) timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pipelines: Option<BTreeMap<&'static str, Vec<ModelStatus>>>
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct ErrorDetail {
+    /// Machine-readable error code, one of the constants in `ErrorCodes`
+    pub(crate) code: String,
+    pub(crate) message: String,
+    /// Accepted values when a language or model was rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) accepted: Option<Vec<String>>
+}
+
+#[derive(Serialize)]
+pub(crate) struct ErrorResponse {
+    pub(crate) status: String,
+    pub(crate) error: ErrorDetail
+}
+
+#[derive(Serialize)]
+pub(crate) struct LanguageInfo {
+    /// ISO 639-1 code, or ISO 639-3 code if the language has no two letter code
+    pub(crate) code: String,
+    pub(crate) iso_639_3: String,
+    pub(crate) name: String
+}
+
+#[derive(Serialize)]
+pub(crate) struct LanguagePairs {
+    pub(crate) source: String,
+    pub(crate) targets: Vec<String>
+}
+
+#[derive(Serialize)]
+pub(crate) struct LanguagesResponse {
+    pub(crate) languages: Vec<LanguageInfo>,
+    pub(crate) pairs: Vec<LanguagePairs>
+}
+
+/// Body of `POST /jobs`: the task type and the request body of its synchronous endpoint.
+#[derive(Deserialize)]
+#[serde(tag = "task", content = "payload", rename_all = "kebab-case")]
+pub(crate) enum JobRequest {
+    Summarize(SummarizationRequest),
+    Translate(TranslationRequest),
+    ZeroShot(ZeroShotRequest),
+    Keywords(KeywordExtractionRequest),
+    Dialogue(DialogueRequest),
+}
+
+impl JobRequest {
+    pub(crate) fn task(&self) -> &'static str {
+        match self {
+            JobRequest::Summarize(_) => "summarize",
+            JobRequest::Translate(_) => "translate",
+            JobRequest::ZeroShot(_) => "zero-shot",
+            JobRequest::Keywords(_) => "keywords",
+            JobRequest::Dialogue(_) => "dialogue",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct JobResponse {
+    pub(crate) id: String,
+    pub(crate) task: String,
+    pub(crate) state: JobState,
+    /// Estimated fraction of the work done, between 0 and 1
+    pub(crate) progress: f32,
+    /// Response body of the synchronous endpoint of the task, once completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<ErrorDetail>
+}